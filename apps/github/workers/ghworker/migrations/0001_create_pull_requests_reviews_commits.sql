@@ -0,0 +1,60 @@
+CREATE TABLE IF NOT EXISTS pull_requests (
+    id TEXT PRIMARY KEY,
+    author_id TEXT NOT NULL,
+    date_opened TIMESTAMPTZ NOT NULL,
+    date_closed TIMESTAMPTZ
+);
+
+CREATE INDEX IF NOT EXISTS pull_requests_author_id_idx ON pull_requests (author_id);
+CREATE INDEX IF NOT EXISTS pull_requests_date_opened_idx ON pull_requests (date_opened);
+CREATE INDEX IF NOT EXISTS pull_requests_date_closed_idx ON pull_requests (date_closed);
+
+CREATE TABLE IF NOT EXISTS reviews (
+    id TEXT PRIMARY KEY,
+    date_published TIMESTAMPTZ NOT NULL,
+    is_owner BOOLEAN NOT NULL,
+    state TEXT NOT NULL,
+    author_id TEXT NOT NULL,
+    pull_request_id TEXT NOT NULL REFERENCES pull_requests (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS reviews_author_id_idx ON reviews (author_id);
+CREATE INDEX IF NOT EXISTS reviews_date_published_idx ON reviews (date_published);
+CREATE INDEX IF NOT EXISTS reviews_pull_request_id_idx ON reviews (pull_request_id);
+
+CREATE TABLE IF NOT EXISTS commits (
+    id TEXT PRIMARY KEY,
+    hash TEXT NOT NULL,
+    date_committed TIMESTAMPTZ NOT NULL,
+    author_id TEXT NOT NULL,
+    pull_request_id TEXT NOT NULL REFERENCES pull_requests (id) ON DELETE CASCADE
+);
+
+CREATE INDEX IF NOT EXISTS commits_author_id_idx ON commits (author_id);
+CREATE INDEX IF NOT EXISTS commits_date_committed_idx ON commits (date_committed);
+CREATE INDEX IF NOT EXISTS commits_pull_request_id_idx ON commits (pull_request_id);
+
+-- Tables created before the migrations existed have no foreign keys. They are
+-- added without checking the rows already stored, which may reference pull
+-- requests that were never saved.
+DO $$
+BEGIN
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conrelid = 'reviews'::regclass AND contype = 'f'
+    ) THEN
+        ALTER TABLE reviews
+            ADD CONSTRAINT reviews_pull_request_id_fkey FOREIGN KEY (pull_request_id)
+            REFERENCES pull_requests (id) ON DELETE CASCADE NOT VALID;
+    END IF;
+
+    IF NOT EXISTS (
+        SELECT 1 FROM pg_constraint
+        WHERE conrelid = 'commits'::regclass AND contype = 'f'
+    ) THEN
+        ALTER TABLE commits
+            ADD CONSTRAINT commits_pull_request_id_fkey FOREIGN KEY (pull_request_id)
+            REFERENCES pull_requests (id) ON DELETE CASCADE NOT VALID;
+    END IF;
+END
+$$;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS sync_state (
+    username TEXT NOT NULL,
+    contribution_type TEXT NOT NULL,
+    last_synced_at TIMESTAMPTZ NOT NULL,
+    PRIMARY KEY (username, contribution_type)
+);
@@ -0,0 +1,10 @@
+ALTER TABLE pull_requests
+    ADD COLUMN title TEXT,
+    ADD COLUMN date_merged TIMESTAMPTZ,
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+ALTER TABLE reviews
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
+
+ALTER TABLE commits
+    ADD COLUMN updated_at TIMESTAMPTZ NOT NULL DEFAULT now();
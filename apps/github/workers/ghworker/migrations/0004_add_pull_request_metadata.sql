@@ -0,0 +1,17 @@
+ALTER TABLE pull_requests
+    ADD COLUMN repository TEXT,
+    ADD COLUMN number BIGINT,
+    ADD COLUMN merged BOOLEAN NOT NULL DEFAULT false,
+    ADD COLUMN additions BIGINT,
+    ADD COLUMN deletions BIGINT,
+    ADD COLUMN changed_files BIGINT,
+    ADD COLUMN base_branch TEXT,
+    ADD COLUMN head_branch TEXT;
+
+CREATE INDEX pull_requests_repository_idx ON pull_requests (repository);
+
+ALTER TABLE reviews
+    ADD COLUMN body TEXT;
+
+ALTER TABLE commits
+    ADD COLUMN message_headline TEXT;
@@ -0,0 +1,18 @@
+ALTER TABLE pull_requests
+    ALTER COLUMN author_id DROP NOT NULL,
+    ADD COLUMN author_kind TEXT NOT NULL DEFAULT 'user',
+    ADD COLUMN author_login TEXT;
+
+ALTER TABLE reviews
+    ALTER COLUMN author_id DROP NOT NULL,
+    ADD COLUMN author_kind TEXT NOT NULL DEFAULT 'user',
+    ADD COLUMN author_login TEXT;
+
+ALTER TABLE commits
+    ALTER COLUMN author_id DROP NOT NULL,
+    ADD COLUMN author_kind TEXT NOT NULL DEFAULT 'user',
+    ADD COLUMN author_login TEXT,
+    ADD COLUMN author_name TEXT,
+    ADD COLUMN author_email TEXT;
+
+UPDATE commits SET author_id = NULL, author_kind = 'unlinked' WHERE author_id = '';
@@ -0,0 +1,20 @@
+CREATE TABLE github_accounts (
+    user_id TEXT PRIMARY KEY,
+    login TEXT NOT NULL,
+    node_id TEXT,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now()
+);
+
+CREATE UNIQUE INDEX github_accounts_node_id_idx ON github_accounts (node_id);
+CREATE INDEX github_accounts_login_idx ON github_accounts (lower(login));
+
+CREATE TABLE user_contributions (
+    user_id TEXT NOT NULL REFERENCES github_accounts (user_id) ON DELETE CASCADE,
+    pull_request_id TEXT NOT NULL REFERENCES pull_requests (id) ON DELETE CASCADE,
+    contribution_type TEXT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, pull_request_id, contribution_type)
+);
+
+CREATE INDEX user_contributions_pull_request_id_idx ON user_contributions (pull_request_id);
@@ -0,0 +1,11 @@
+CREATE TABLE contribution_days (
+    user_id TEXT NOT NULL REFERENCES github_accounts (user_id) ON DELETE CASCADE,
+    date DATE NOT NULL,
+    weekday SMALLINT NOT NULL,
+    contribution_count INTEGER NOT NULL,
+    color TEXT NOT NULL,
+    level SMALLINT NOT NULL,
+    created_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    updated_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+    PRIMARY KEY (user_id, date)
+);
@@ -0,0 +1,15 @@
+ALTER TABLE sync_state
+    ADD COLUMN user_id TEXT REFERENCES github_accounts (user_id) ON DELETE CASCADE;
+
+UPDATE sync_state
+SET user_id = github_accounts.user_id
+FROM github_accounts
+WHERE lower(github_accounts.login) = lower(sync_state.username);
+
+DELETE FROM sync_state WHERE user_id IS NULL;
+
+ALTER TABLE sync_state
+    DROP CONSTRAINT sync_state_pkey,
+    DROP COLUMN username,
+    ALTER COLUMN user_id SET NOT NULL,
+    ADD PRIMARY KEY (user_id, contribution_type);
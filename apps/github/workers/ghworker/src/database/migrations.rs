@@ -0,0 +1,106 @@
+use anyhow::*;
+use log::{debug, info};
+use sqlx::postgres::PgRow;
+use sqlx::{Executor, PgPool, Row};
+
+struct Migration {
+    version: i64,
+    name: &'static str,
+    sql: &'static str,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_pull_requests_reviews_commits",
+        sql: include_str!("../../migrations/0001_create_pull_requests_reviews_commits.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_sync_state",
+        sql: include_str!("../../migrations/0002_create_sync_state.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "track_pull_request_updates",
+        sql: include_str!("../../migrations/0003_track_pull_request_updates.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "add_pull_request_metadata",
+        sql: include_str!("../../migrations/0004_add_pull_request_metadata.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "add_author_kinds",
+        sql: include_str!("../../migrations/0005_add_author_kinds.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "create_github_accounts",
+        sql: include_str!("../../migrations/0006_create_github_accounts.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "create_contribution_days",
+        sql: include_str!("../../migrations/0007_create_contribution_days.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "key_sync_state_by_user",
+        sql: include_str!("../../migrations/0008_key_sync_state_by_user.sql"),
+    },
+];
+
+pub async fn migrate(db_pool: &PgPool) -> Result<i64> {
+    let mut tx = db_pool.begin().await?;
+
+    tx.execute(
+        r#"
+        CREATE TABLE IF NOT EXISTS schema_migrations (
+            version BIGINT PRIMARY KEY,
+            name TEXT NOT NULL,
+            applied_at TIMESTAMPTZ NOT NULL DEFAULT now()
+        );
+        LOCK TABLE schema_migrations IN EXCLUSIVE MODE;
+        "#,
+    )
+    .await
+    .context("Preparing the schema_migrations table")?;
+
+    let current_version: i64 =
+        sqlx::query("SELECT COALESCE(MAX(version), 0) AS version FROM schema_migrations")
+            .map(|row: PgRow| row.get("version"))
+            .fetch_one(&mut tx)
+            .await?;
+    debug!("The database is at schema version {}", current_version);
+
+    let mut applied_version = current_version;
+
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|migration| migration.version > current_version)
+    {
+        info!(
+            "Applying migration {} ({})",
+            migration.version, migration.name
+        );
+
+        tx.execute(migration.sql).await.context(format!(
+            "Applying migration {} ({})",
+            migration.version, migration.name
+        ))?;
+
+        sqlx::query("INSERT INTO schema_migrations (version, name) VALUES ($1, $2)")
+            .bind(migration.version)
+            .bind(migration.name)
+            .execute(&mut tx)
+            .await?;
+
+        applied_version = migration.version;
+    }
+
+    tx.commit().await?;
+
+    Ok(applied_version)
+}
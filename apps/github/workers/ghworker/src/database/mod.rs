@@ -1 +1,5 @@
+pub mod migrations;
 pub mod repository;
+
+pub type PgTransaction =
+    sqlx::Transaction<sqlx::pool::PoolConnection<sqlx::postgres::PgConnection>>;
@@ -1,124 +1,946 @@
-use crate::dto::pull_requests::{Commit, PullRequest, Review};
+use crate::database::PgTransaction;
+use crate::dto::pull_requests::{Author, AuthorKind, Commit, PullRequest, Review, ReviewState};
+use crate::dto::{ContributionDay, ContributionType};
 use anyhow::*;
-use chrono::offset::FixedOffset;
-use chrono::DateTime;
-use log::debug;
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use log::{debug, warn};
+use serde::Serialize;
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::collections::HashMap;
+
+const MAX_BIND_PARAMETERS: usize = 65535;
+
+pub type StoredColumns = Vec<(&'static str, Option<String>)>;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+    Unchanged,
+}
+
+impl UpsertOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            UpsertOutcome::Inserted => "inserted",
+            UpsertOutcome::Updated => "updated",
+            UpsertOutcome::Unchanged => "unchanged",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct UpsertCounts {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+}
+
+impl UpsertCounts {
+    pub fn add(&mut self, other: &UpsertCounts) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.unchanged += other.unchanged;
+    }
+
+    fn record(&mut self, outcome: UpsertOutcome) {
+        match outcome {
+            UpsertOutcome::Inserted => self.inserted += 1,
+            UpsertOutcome::Updated => self.updated += 1,
+            UpsertOutcome::Unchanged => self.unchanged += 1,
+        }
+    }
+}
 
 pub struct CommitRepository {}
 
 impl CommitRepository {
-    pub async fn create(
+    const COLUMNS: usize = 10;
+
+    pub async fn find_by_pull_request_ids(
         db_pool: &PgPool,
-        pull_request: &PullRequest,
-        commit: &Commit,
-    ) -> Result<()> {
-        debug!("About to save the commit => {}", &commit.id);
+        pull_request_ids: &[String],
+    ) -> Result<Vec<(String, Commit)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, hash, message_headline, date_committed, author_id, author_kind,
+                author_login, author_name, author_email, pull_request_id
+            FROM commits
+            WHERE pull_request_id = ANY($1)
+            ORDER BY date_committed, id
+            "#,
+        )
+        .bind(pull_request_ids)
+        .map(|row: PgRow| -> Result<(String, Commit)> {
+            Ok((
+                row.get("pull_request_id"),
+                Commit {
+                    id: row.get("id"),
+                    hash: row.get("hash"),
+                    message_headline: row
+                        .get::<Option<String>, _>("message_headline")
+                        .unwrap_or_default(),
+                    author: Author {
+                        name: row.get("author_name"),
+                        email: row.get("author_email"),
+                        ..stored_author(&row)?
+                    },
+                    occurred_at: row.get("date_committed"),
+                },
+            ))
+        })
+        .fetch_all(db_pool)
+        .await?;
 
-        let mut tx = db_pool.begin().await?;
+        rows.into_iter().collect()
+    }
 
-        let modified_records = sqlx::query(
+    pub async fn find_by_ids(
+        db_pool: &PgPool,
+        ids: &[String],
+    ) -> Result<HashMap<String, StoredColumns>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO commits (id, hash, date_committed, author_id, pull_request_id)
-            VALUES ($1, $2, $3, $4, $5)
-            ON CONFLICT (id) DO NOTHING
+            SELECT id, message_headline, date_committed, author_id, author_kind,
+                author_login, author_name, author_email
+            FROM commits
+            WHERE id = ANY($1)
             "#,
         )
-        .bind(&commit.id)
-        .bind(&commit.hash)
-        .bind(DateTime::parse_from_rfc3339(&commit.occurred_at)?)
-        .bind(&commit.author_id)
-        .bind(&pull_request.id)
-        .execute(&mut tx)
+        .bind(ids)
+        .map(|row: PgRow| {
+            (
+                row.get("id"),
+                vec![
+                    ("message_headline", row.get("message_headline")),
+                    (
+                        "date_committed",
+                        text(Some(row.get::<DateTime<Utc>, _>("date_committed"))),
+                    ),
+                    ("author_id", row.get("author_id")),
+                    (
+                        "author_kind",
+                        text(Some(row.get::<String, _>("author_kind"))),
+                    ),
+                    ("author_login", row.get("author_login")),
+                    ("author_name", row.get("author_name")),
+                    ("author_email", row.get("author_email")),
+                ],
+            )
+        })
+        .fetch_all(db_pool)
         .await?;
 
-        tx.commit().await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn upsert_many(
+        tx: &mut PgTransaction,
+        commits: &[(&PullRequest, &Commit)],
+        chunk_size: usize,
+    ) -> Result<UpsertCounts> {
+        let mut counts = UpsertCounts::default();
+
+        for chunk in commits.chunks(bounded_chunk_size(chunk_size, Self::COLUMNS)) {
+            debug!("About to save {} commits", chunk.len());
 
-        if modified_records > 0 {
-            debug!("Commit {} added to the datbase", &commit.id);
-        } else {
-            debug!("Commit {} already existed", &commit.id);
+            let sql = format!(
+                r#"
+                INSERT INTO commits (
+                    id, hash, message_headline, date_committed, author_id, author_kind,
+                    author_login, author_name, author_email, pull_request_id
+                )
+                VALUES {}
+                ON CONFLICT (id) DO UPDATE SET
+                    message_headline = EXCLUDED.message_headline,
+                    date_committed = EXCLUDED.date_committed,
+                    author_id = EXCLUDED.author_id,
+                    author_kind = EXCLUDED.author_kind,
+                    author_login = EXCLUDED.author_login,
+                    author_name = EXCLUDED.author_name,
+                    author_email = EXCLUDED.author_email,
+                    updated_at = now()
+                WHERE (
+                    commits.message_headline, commits.date_committed, commits.author_id,
+                    commits.author_kind, commits.author_login, commits.author_name,
+                    commits.author_email
+                ) IS DISTINCT FROM (
+                    EXCLUDED.message_headline, EXCLUDED.date_committed, EXCLUDED.author_id,
+                    EXCLUDED.author_kind, EXCLUDED.author_login, EXCLUDED.author_name,
+                    EXCLUDED.author_email
+                )
+                RETURNING id, (xmax = 0) AS inserted
+                "#,
+                values_placeholders(chunk.len(), Self::COLUMNS)
+            );
+            let mut query = sqlx::query(&sql);
+
+            for (pull_request, commit) in chunk {
+                query = query
+                    .bind(&commit.id)
+                    .bind(&commit.hash)
+                    .bind(&commit.message_headline)
+                    .bind(commit.occurred_at)
+                    .bind(&commit.author.id)
+                    .bind(commit.author.kind.as_str())
+                    .bind(&commit.author.login)
+                    .bind(&commit.author.name)
+                    .bind(&commit.author.email)
+                    .bind(&pull_request.id);
+            }
+
+            let returned = query
+                .map(|row: PgRow| (row.get("id"), row.get("inserted")))
+                .fetch_all(&mut *tx)
+                .await?;
+
+            record_outcomes(
+                "Commit",
+                chunk.iter().map(|(_, commit)| commit.id.as_str()),
+                returned,
+                &mut counts,
+            );
         }
 
-        Ok(())
+        Ok(counts)
     }
 }
 
 pub struct ReviewRepository {}
 
 impl ReviewRepository {
-    pub async fn create(
+    const COLUMNS: usize = 9;
+
+    pub async fn find_by_pull_request_ids(
         db_pool: &PgPool,
-        pull_request: &PullRequest,
-        review: &Review,
-    ) -> Result<()> {
-        debug!("About to save the review => {}", &review.id);
+        pull_request_ids: &[String],
+    ) -> Result<Vec<(String, Review)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, date_published, is_owner, state, body, author_id, author_kind,
+                author_login, pull_request_id
+            FROM reviews
+            WHERE pull_request_id = ANY($1)
+            ORDER BY date_published, id
+            "#,
+        )
+        .bind(pull_request_ids)
+        .map(|row: PgRow| -> Result<(String, Review)> {
+            let state: String = row.get("state");
+
+            Ok((
+                row.get("pull_request_id"),
+                Review {
+                    id: row.get("id"),
+                    occurred_at: row.get("date_published"),
+                    viewer_did_author: row.get("is_owner"),
+                    state: ReviewState::parse(&state)
+                        .ok_or_else(|| anyhow!("Unknown review state {}", state))?,
+                    body: row.get::<Option<String>, _>("body").unwrap_or_default(),
+                    author: stored_author(&row)?,
+                },
+            ))
+        })
+        .fetch_all(db_pool)
+        .await?;
 
-        let mut tx = db_pool.begin().await?;
+        rows.into_iter().collect()
+    }
 
-        let modified_records = sqlx::query(
+    pub async fn find_by_ids(
+        db_pool: &PgPool,
+        ids: &[String],
+    ) -> Result<HashMap<String, StoredColumns>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO reviews (id, date_published, is_owner, state, author_id, pull_request_id)
-            VALUES ($1, $2, $3, $4, $5, $6)
-            ON CONFLICT (id) DO NOTHING
+            SELECT id, state, body, author_id, author_kind, author_login
+            FROM reviews
+            WHERE id = ANY($1)
             "#,
         )
-        .bind(&review.id)
-        .bind(DateTime::parse_from_rfc3339(&review.occurred_at)?)
-        .bind(&review.viewer_did_author)
-        .bind(&review.state)
-        .bind(&review.author_id)
-        .bind(&pull_request.id)
-        .execute(&mut tx)
+        .bind(ids)
+        .map(|row: PgRow| {
+            (
+                row.get("id"),
+                vec![
+                    ("state", text(Some(row.get::<String, _>("state")))),
+                    ("body", row.get("body")),
+                    ("author_id", row.get("author_id")),
+                    (
+                        "author_kind",
+                        text(Some(row.get::<String, _>("author_kind"))),
+                    ),
+                    ("author_login", row.get("author_login")),
+                ],
+            )
+        })
+        .fetch_all(db_pool)
         .await?;
 
-        tx.commit().await?;
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn upsert_many(
+        tx: &mut PgTransaction,
+        reviews: &[(&PullRequest, &Review)],
+        chunk_size: usize,
+    ) -> Result<UpsertCounts> {
+        let mut counts = UpsertCounts::default();
 
-        if modified_records > 0 {
-            debug!("Review {} added to the datbase", &review.id);
-        } else {
-            debug!("Review {} already existed", &review.id);
+        for chunk in reviews.chunks(bounded_chunk_size(chunk_size, Self::COLUMNS)) {
+            debug!("About to save {} reviews", chunk.len());
+
+            let sql = format!(
+                r#"
+                INSERT INTO reviews (
+                    id, date_published, is_owner, state, body, author_id, author_kind,
+                    author_login, pull_request_id
+                )
+                VALUES {}
+                ON CONFLICT (id) DO UPDATE SET
+                    state = EXCLUDED.state,
+                    body = EXCLUDED.body,
+                    author_id = EXCLUDED.author_id,
+                    author_kind = EXCLUDED.author_kind,
+                    author_login = EXCLUDED.author_login,
+                    updated_at = now()
+                WHERE (
+                    reviews.state, reviews.body, reviews.author_id, reviews.author_kind,
+                    reviews.author_login
+                ) IS DISTINCT FROM (
+                    EXCLUDED.state, EXCLUDED.body, EXCLUDED.author_id, EXCLUDED.author_kind,
+                    EXCLUDED.author_login
+                )
+                RETURNING id, (xmax = 0) AS inserted
+                "#,
+                values_placeholders(chunk.len(), Self::COLUMNS)
+            );
+            let mut query = sqlx::query(&sql);
+
+            for (pull_request, review) in chunk {
+                query = query
+                    .bind(&review.id)
+                    .bind(review.occurred_at)
+                    .bind(review.viewer_did_author)
+                    .bind(review.state.as_str())
+                    .bind(&review.body)
+                    .bind(&review.author.id)
+                    .bind(review.author.kind.as_str())
+                    .bind(&review.author.login)
+                    .bind(&pull_request.id);
+            }
+
+            let returned = query
+                .map(|row: PgRow| (row.get("id"), row.get("inserted")))
+                .fetch_all(&mut *tx)
+                .await?;
+
+            record_outcomes(
+                "Review",
+                chunk.iter().map(|(_, review)| review.id.as_str()),
+                returned,
+                &mut counts,
+            );
         }
 
-        Ok(())
+        Ok(counts)
     }
 }
 
 pub struct PullRequestRepository {}
 
 impl PullRequestRepository {
-    pub async fn create(db_pool: &PgPool, pull_request: &PullRequest) -> Result<()> {
-        debug!("About to save the pull request => {}", &pull_request.id);
+    const COLUMNS: usize = 16;
+
+    pub async fn find_for_user(
+        db_pool: &PgPool,
+        user_id: &str,
+        login: &str,
+    ) -> Result<Vec<PullRequest>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, author_id, author_kind, author_login, repository, number, title,
+                date_opened, date_closed, date_merged, merged, additions, deletions,
+                changed_files, base_branch, head_branch
+            FROM pull_requests
+            WHERE id IN (SELECT pull_request_id FROM user_contributions WHERE user_id = $1)
+            OR lower(author_login) = lower($2)
+            ORDER BY date_opened, id
+            "#,
+        )
+        .bind(user_id)
+        .bind(login)
+        .map(|row: PgRow| -> Result<PullRequest> {
+            Ok(PullRequest {
+                id: row.get("id"),
+                author: stored_author(&row)?,
+                title: row.get::<Option<String>, _>("title").unwrap_or_default(),
+                date_opened: row.get("date_opened"),
+                date_closed: row.get("date_closed"),
+                date_merged: row.get("date_merged"),
+                merged: row.get("merged"),
+                number: row.get::<Option<i64>, _>("number").unwrap_or_default(),
+                repository: row
+                    .get::<Option<String>, _>("repository")
+                    .unwrap_or_default(),
+                additions: row.get::<Option<i64>, _>("additions").unwrap_or_default(),
+                deletions: row.get::<Option<i64>, _>("deletions").unwrap_or_default(),
+                changed_files: row
+                    .get::<Option<i64>, _>("changed_files")
+                    .unwrap_or_default(),
+                base_branch: row
+                    .get::<Option<String>, _>("base_branch")
+                    .unwrap_or_default(),
+                head_branch: row
+                    .get::<Option<String>, _>("head_branch")
+                    .unwrap_or_default(),
+                reviews: Vec::new(),
+                commits: Vec::new(),
+            })
+        })
+        .fetch_all(db_pool)
+        .await?;
+
+        rows.into_iter().collect()
+    }
+
+    pub async fn find_open_ids_for_user(
+        db_pool: &PgPool,
+        user_id: &str,
+        login: &str,
+    ) -> Result<Vec<String>> {
+        let ids = sqlx::query(
+            r#"
+            SELECT id FROM pull_requests
+            WHERE date_closed IS NULL
+            AND (
+                id IN (SELECT pull_request_id FROM user_contributions WHERE user_id = $1)
+                OR lower(author_login) = lower($2)
+            )
+            ORDER BY date_opened, id
+            "#,
+        )
+        .bind(user_id)
+        .bind(login)
+        .map(|row: PgRow| row.get("id"))
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(ids)
+    }
+
+    pub async fn find_by_ids(
+        db_pool: &PgPool,
+        ids: &[String],
+    ) -> Result<HashMap<String, StoredColumns>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT id, author_id, author_kind, author_login, repository, number, title,
+                date_closed, date_merged, merged, additions, deletions, changed_files,
+                base_branch, head_branch
+            FROM pull_requests
+            WHERE id = ANY($1)
+            "#,
+        )
+        .bind(ids)
+        .map(|row: PgRow| {
+            (
+                row.get("id"),
+                vec![
+                    ("author_id", row.get("author_id")),
+                    (
+                        "author_kind",
+                        text(Some(row.get::<String, _>("author_kind"))),
+                    ),
+                    ("author_login", row.get("author_login")),
+                    ("repository", row.get("repository")),
+                    ("number", text(row.get::<Option<i64>, _>("number"))),
+                    ("title", row.get("title")),
+                    (
+                        "date_closed",
+                        text(row.get::<Option<DateTime<Utc>>, _>("date_closed")),
+                    ),
+                    (
+                        "date_merged",
+                        text(row.get::<Option<DateTime<Utc>>, _>("date_merged")),
+                    ),
+                    ("merged", text(Some(row.get::<bool, _>("merged")))),
+                    ("additions", text(row.get::<Option<i64>, _>("additions"))),
+                    ("deletions", text(row.get::<Option<i64>, _>("deletions"))),
+                    (
+                        "changed_files",
+                        text(row.get::<Option<i64>, _>("changed_files")),
+                    ),
+                    ("base_branch", row.get("base_branch")),
+                    ("head_branch", row.get("head_branch")),
+                ],
+            )
+        })
+        .fetch_all(db_pool)
+        .await?;
+
+        Ok(rows.into_iter().collect())
+    }
+
+    pub async fn upsert_many(
+        tx: &mut PgTransaction,
+        pull_requests: &[&PullRequest],
+        chunk_size: usize,
+    ) -> Result<UpsertCounts> {
+        let mut counts = UpsertCounts::default();
+
+        for chunk in pull_requests.chunks(bounded_chunk_size(chunk_size, Self::COLUMNS)) {
+            debug!("About to save {} pull requests", chunk.len());
+
+            let sql = format!(
+                r#"
+                INSERT INTO pull_requests (
+                    id, author_id, author_kind, author_login, repository, number, title,
+                    date_opened, date_closed, date_merged, merged, additions, deletions,
+                    changed_files, base_branch, head_branch
+                )
+                VALUES {}
+                ON CONFLICT (id) DO UPDATE SET
+                    author_id = EXCLUDED.author_id,
+                    author_kind = EXCLUDED.author_kind,
+                    author_login = EXCLUDED.author_login,
+                    repository = EXCLUDED.repository,
+                    number = EXCLUDED.number,
+                    title = EXCLUDED.title,
+                    date_closed = EXCLUDED.date_closed,
+                    date_merged = EXCLUDED.date_merged,
+                    merged = EXCLUDED.merged,
+                    additions = EXCLUDED.additions,
+                    deletions = EXCLUDED.deletions,
+                    changed_files = EXCLUDED.changed_files,
+                    base_branch = EXCLUDED.base_branch,
+                    head_branch = EXCLUDED.head_branch,
+                    updated_at = now()
+                WHERE (
+                    pull_requests.author_id, pull_requests.author_kind, pull_requests.author_login,
+                    pull_requests.repository, pull_requests.number, pull_requests.title,
+                    pull_requests.date_closed, pull_requests.date_merged, pull_requests.merged,
+                    pull_requests.additions, pull_requests.deletions, pull_requests.changed_files,
+                    pull_requests.base_branch, pull_requests.head_branch
+                ) IS DISTINCT FROM (
+                    EXCLUDED.author_id, EXCLUDED.author_kind, EXCLUDED.author_login,
+                    EXCLUDED.repository, EXCLUDED.number, EXCLUDED.title,
+                    EXCLUDED.date_closed, EXCLUDED.date_merged, EXCLUDED.merged,
+                    EXCLUDED.additions, EXCLUDED.deletions, EXCLUDED.changed_files,
+                    EXCLUDED.base_branch, EXCLUDED.head_branch
+                )
+                RETURNING id, (xmax = 0) AS inserted
+                "#,
+                values_placeholders(chunk.len(), Self::COLUMNS)
+            );
+            let mut query = sqlx::query(&sql);
+
+            for pull_request in chunk {
+                query = query
+                    .bind(&pull_request.id)
+                    .bind(&pull_request.author.id)
+                    .bind(pull_request.author.kind.as_str())
+                    .bind(&pull_request.author.login)
+                    .bind(&pull_request.repository)
+                    .bind(pull_request.number)
+                    .bind(&pull_request.title)
+                    .bind(pull_request.date_opened)
+                    .bind(pull_request.date_closed)
+                    .bind(pull_request.date_merged)
+                    .bind(pull_request.merged)
+                    .bind(pull_request.additions)
+                    .bind(pull_request.deletions)
+                    .bind(pull_request.changed_files)
+                    .bind(&pull_request.base_branch)
+                    .bind(&pull_request.head_branch);
+            }
+
+            let returned = query
+                .map(|row: PgRow| (row.get("id"), row.get("inserted")))
+                .fetch_all(&mut *tx)
+                .await?;
+
+            record_outcomes(
+                "Pull request",
+                chunk.iter().map(|pull_request| pull_request.id.as_str()),
+                returned,
+                &mut counts,
+            );
+        }
+
+        Ok(counts)
+    }
+
+    pub async fn delete_for_user(
+        tx: &mut PgTransaction,
+        user_id: &str,
+        login: &str,
+    ) -> Result<u64> {
+        let deleted = sqlx::query(
+            r#"
+            DELETE FROM pull_requests
+            WHERE (
+                id IN (SELECT pull_request_id FROM user_contributions WHERE user_id = $1)
+                OR lower(author_login) = lower($2)
+            )
+            AND NOT EXISTS (
+                SELECT 1 FROM user_contributions
+                WHERE user_contributions.pull_request_id = pull_requests.id
+                AND user_contributions.user_id <> $1
+            )
+            "#,
+        )
+        .bind(user_id)
+        .bind(login)
+        .execute(&mut *tx)
+        .await?;
+
+        Ok(deleted)
+    }
+}
+
+pub struct GithubAccountRepository {}
 
-        let mut tx = db_pool.begin().await?;
-        let mut date_closed = None::<DateTime<FixedOffset>>;
+impl GithubAccountRepository {
+    pub async fn find(db_pool: &PgPool, user_id: &str) -> Result<Option<Author>> {
+        let account = sqlx::query("SELECT login, node_id FROM github_accounts WHERE user_id = $1")
+            .bind(user_id)
+            .map(|row: PgRow| Author {
+                kind: AuthorKind::User,
+                id: row.get("node_id"),
+                login: row.get("login"),
+                name: None,
+                email: None,
+            })
+            .fetch_optional(db_pool)
+            .await?;
 
-        if let Some(date) = &pull_request.date_closed {
-            date_closed = Some(DateTime::parse_from_rfc3339(date)?);
+        Ok(account)
+    }
+
+    pub async fn find_user_id(db_pool: &PgPool, login: &str) -> Result<Option<String>> {
+        let user_id = sqlx::query(
+            r#"
+            SELECT user_id FROM github_accounts
+            WHERE lower(login) = lower($1)
+            ORDER BY node_id IS NULL, updated_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(login)
+        .map(|row: PgRow| row.get("user_id"))
+        .fetch_optional(db_pool)
+        .await?;
+
+        Ok(user_id)
+    }
+
+    pub async fn upsert(
+        tx: &mut PgTransaction,
+        user_id: &str,
+        login: &str,
+        node_id: Option<&str>,
+    ) -> Result<()> {
+        if let Some(node_id) = node_id {
+            let released = sqlx::query(
+                r#"
+                UPDATE github_accounts SET node_id = NULL
+                WHERE node_id = $1 AND user_id <> $2
+                "#,
+            )
+            .bind(node_id)
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+            if released > 0 {
+                warn!(
+                    "The github account {} was linked to another user, moving it to {}",
+                    login, user_id
+                );
+            }
         }
 
-        let modified_records = sqlx::query(
+        sqlx::query(
+            r#"
+            INSERT INTO github_accounts (user_id, login, node_id)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id) DO UPDATE SET
+                login = EXCLUDED.login,
+                node_id = COALESCE(EXCLUDED.node_id, github_accounts.node_id),
+                updated_at = now()
+            WHERE (github_accounts.login, github_accounts.node_id)
+                IS DISTINCT FROM (EXCLUDED.login, COALESCE(EXCLUDED.node_id, github_accounts.node_id))
+            "#,
+        )
+        .bind(user_id)
+        .bind(login)
+        .bind(node_id)
+        .execute(&mut *tx)
+        .await?;
+
+        debug!(
+            "Linked the user {} to the github account {}",
+            user_id, login
+        );
+
+        Ok(())
+    }
+
+    pub async fn delete(tx: &mut PgTransaction, user_id: &str) -> Result<u64> {
+        let deleted = sqlx::query("DELETE FROM github_accounts WHERE user_id = $1")
+            .bind(user_id)
+            .execute(&mut *tx)
+            .await?;
+
+        Ok(deleted)
+    }
+}
+
+pub struct UserContributionRepository {}
+
+impl UserContributionRepository {
+    const COLUMNS: usize = 3;
+
+    pub async fn find_for_user(
+        db_pool: &PgPool,
+        user_id: &str,
+    ) -> Result<Vec<(String, ContributionType)>> {
+        let rows = sqlx::query(
             r#"
-            INSERT INTO pull_requests (id, author_id, date_opened, date_closed)
-            VALUES ($1, $2, $3, $4)
-            ON CONFLICT (id) DO NOTHING
+            SELECT pull_request_id, contribution_type FROM user_contributions
+            WHERE user_id = $1
             "#,
         )
-        .bind(&pull_request.id)
-        .bind(&pull_request.author_id)
-        .bind(DateTime::parse_from_rfc3339(&pull_request.date_opened)?)
-        .bind(date_closed)
-        .execute(&mut tx)
+        .bind(user_id)
+        .map(|row: PgRow| -> Result<(String, ContributionType)> {
+            let contribution_type: String = row.get("contribution_type");
+
+            Ok((
+                row.get("pull_request_id"),
+                ContributionType::parse(&contribution_type)
+                    .ok_or_else(|| anyhow!("Unknown contribution type {}", contribution_type))?,
+            ))
+        })
+        .fetch_all(db_pool)
         .await?;
 
-        tx.commit().await?;
+        rows.into_iter().collect()
+    }
+
+    pub async fn insert_many(
+        tx: &mut PgTransaction,
+        user_id: &str,
+        contributions: &[&(String, ContributionType)],
+        chunk_size: usize,
+    ) -> Result<u64> {
+        let mut inserted = 0;
+
+        for chunk in contributions.chunks(bounded_chunk_size(chunk_size, Self::COLUMNS)) {
+            debug!("About to save {} contributions of {}", chunk.len(), user_id);
 
-        if modified_records > 0 {
-            debug!("Pull request {} added to the datbase", &pull_request.id);
-        } else {
-            debug!("Pull request {} already existed", &pull_request.id);
+            let sql = format!(
+                r#"
+                INSERT INTO user_contributions (user_id, pull_request_id, contribution_type)
+                VALUES {}
+                ON CONFLICT DO NOTHING
+                "#,
+                values_placeholders(chunk.len(), Self::COLUMNS)
+            );
+            let mut query = sqlx::query(&sql);
+
+            for (pull_request_id, contribution_type) in chunk.iter() {
+                query = query
+                    .bind(user_id)
+                    .bind(pull_request_id)
+                    .bind(contribution_type.as_str());
+            }
+
+            inserted += query.execute(&mut *tx).await?;
         }
 
+        Ok(inserted)
+    }
+}
+
+pub struct ContributionDayRepository {}
+
+impl ContributionDayRepository {
+    const COLUMNS: usize = 6;
+
+    pub async fn upsert_many(
+        tx: &mut PgTransaction,
+        user_id: &str,
+        days: &[ContributionDay],
+        chunk_size: usize,
+    ) -> Result<UpsertCounts> {
+        let mut counts = UpsertCounts::default();
+
+        for chunk in days.chunks(bounded_chunk_size(chunk_size, Self::COLUMNS)) {
+            debug!(
+                "About to save {} contribution days of {}",
+                chunk.len(),
+                user_id
+            );
+
+            let sql = format!(
+                r#"
+                INSERT INTO contribution_days (
+                    user_id, date, weekday, contribution_count, color, level
+                )
+                VALUES {}
+                ON CONFLICT (user_id, date) DO UPDATE SET
+                    weekday = EXCLUDED.weekday,
+                    contribution_count = EXCLUDED.contribution_count,
+                    color = EXCLUDED.color,
+                    level = EXCLUDED.level,
+                    updated_at = now()
+                WHERE (
+                    contribution_days.weekday, contribution_days.contribution_count,
+                    contribution_days.color, contribution_days.level
+                ) IS DISTINCT FROM (
+                    EXCLUDED.weekday, EXCLUDED.contribution_count, EXCLUDED.color,
+                    EXCLUDED.level
+                )
+                RETURNING to_char(date, 'YYYY-MM-DD') AS id, (xmax = 0) AS inserted
+                "#,
+                values_placeholders(chunk.len(), Self::COLUMNS)
+            );
+            let mut query = sqlx::query(&sql);
+
+            for day in chunk {
+                query = query
+                    .bind(user_id)
+                    .bind(day.date)
+                    .bind(day.weekday)
+                    .bind(day.count)
+                    .bind(&day.color)
+                    .bind(day.level);
+            }
+
+            let returned = query
+                .map(|row: PgRow| (row.get("id"), row.get("inserted")))
+                .fetch_all(&mut *tx)
+                .await?;
+            let dates: Vec<String> = chunk
+                .iter()
+                .map(|day| day.date.format("%Y-%m-%d").to_string())
+                .collect();
+
+            record_outcomes(
+                "Contribution day",
+                dates.iter().map(String::as_str),
+                returned,
+                &mut counts,
+            );
+        }
+
+        Ok(counts)
+    }
+}
+
+pub struct SyncStateRepository {}
+
+impl SyncStateRepository {
+    pub async fn last_synced_at(
+        db_pool: &PgPool,
+        user_id: &str,
+        contribution_type: ContributionType,
+    ) -> Result<Option<DateTime<Utc>>> {
+        let last_synced_at = sqlx::query(
+            r#"
+            SELECT last_synced_at FROM sync_state
+            WHERE user_id = $1 AND contribution_type = $2
+            "#,
+        )
+        .bind(user_id)
+        .bind(contribution_type.as_str())
+        .map(|row: PgRow| row.get("last_synced_at"))
+        .fetch_optional(db_pool)
+        .await?;
+
+        Ok(last_synced_at)
+    }
+
+    pub async fn record(
+        tx: &mut PgTransaction,
+        user_id: &str,
+        contribution_type: ContributionType,
+        synced_at: DateTime<Utc>,
+    ) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO sync_state (user_id, contribution_type, last_synced_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT (user_id, contribution_type)
+            DO UPDATE SET last_synced_at = EXCLUDED.last_synced_at
+            "#,
+        )
+        .bind(user_id)
+        .bind(contribution_type.as_str())
+        .bind(synced_at)
+        .execute(&mut *tx)
+        .await?;
+
+        debug!(
+            "Recorded the {} sync for {} at {}",
+            contribution_type.as_str(),
+            user_id,
+            synced_at
+        );
+
         Ok(())
     }
 }
+
+fn bounded_chunk_size(chunk_size: usize, columns: usize) -> usize {
+    chunk_size.min(MAX_BIND_PARAMETERS / columns).max(1)
+}
+
+fn values_placeholders(rows: usize, columns: usize) -> String {
+    (0..rows)
+        .map(|row| {
+            let placeholders: Vec<String> = (1..=columns)
+                .map(|column| format!("${}", row * columns + column))
+                .collect();
+            format!("({})", placeholders.join(", "))
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+fn record_outcomes<'a>(
+    kind: &str,
+    ids: impl Iterator<Item = &'a str>,
+    returned: Vec<(String, bool)>,
+    counts: &mut UpsertCounts,
+) {
+    let returned: HashMap<String, bool> = returned.into_iter().collect();
+
+    for id in ids {
+        let outcome = match returned.get(id) {
+            Some(true) => UpsertOutcome::Inserted,
+            Some(false) => UpsertOutcome::Updated,
+            None => UpsertOutcome::Unchanged,
+        };
+        debug!("{} {} {}", kind, id, outcome.as_str());
+        counts.record(outcome);
+    }
+}
+
+fn stored_author(row: &PgRow) -> Result<Author> {
+    let kind: String = row.get("author_kind");
+
+    Ok(Author {
+        kind: AuthorKind::parse(&kind).ok_or_else(|| anyhow!("Unknown author kind {}", kind))?,
+        id: row.get("author_id"),
+        login: row.get("author_login"),
+        name: None,
+        email: None,
+    })
+}
+
+pub fn text<T: ToString>(value: Option<T>) -> Option<String> {
+    value.map(|value| value.to_string())
+}
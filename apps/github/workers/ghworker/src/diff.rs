@@ -0,0 +1,324 @@
+use crate::database::repository::{
+    text, CommitRepository, PullRequestRepository, ReviewRepository, StoredColumns, UpsertOutcome,
+};
+use crate::dto::pull_requests::{Commit, PullRequest, PullRequestsDTO, Review};
+use crate::store::{unique_commits, unique_reviews, StoreSummary};
+use anyhow::*;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone)]
+pub struct ColumnChange {
+    pub column: &'static str,
+    pub from: Option<String>,
+    pub to: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct RowDiff {
+    pub id: String,
+    pub label: String,
+    pub outcome: UpsertOutcome,
+    pub changes: Vec<ColumnChange>,
+}
+
+#[derive(Debug, Default)]
+pub struct SyncDiff {
+    pub pull_requests: Vec<RowDiff>,
+    pub reviews: Vec<RowDiff>,
+    pub commits: Vec<RowDiff>,
+}
+
+impl SyncDiff {
+    pub fn summary(&self) -> StoreSummary {
+        let mut summary = StoreSummary::default();
+
+        for (rows, counts) in [
+            (&self.pull_requests, &mut summary.pull_requests),
+            (&self.reviews, &mut summary.reviews),
+            (&self.commits, &mut summary.commits),
+        ] {
+            for row in rows {
+                match row.outcome {
+                    UpsertOutcome::Inserted => counts.inserted += 1,
+                    UpsertOutcome::Updated => counts.updated += 1,
+                    UpsertOutcome::Unchanged => counts.unchanged += 1,
+                }
+            }
+        }
+
+        summary
+    }
+}
+
+pub async fn diff_pull_requests_dto(
+    db_pool: &PgPool,
+    pull_requests_dto: &PullRequestsDTO,
+) -> Result<SyncDiff> {
+    let pull_requests: Vec<&PullRequest> = pull_requests_dto.pull_requests.values().collect();
+    let reviews = unique_reviews(&pull_requests);
+    let commits = unique_commits(&pull_requests);
+
+    let stored_pull_requests = PullRequestRepository::find_by_ids(
+        db_pool,
+        &pull_requests
+            .iter()
+            .map(|pull_request| pull_request.id.clone())
+            .collect::<Vec<String>>(),
+    )
+    .await?;
+    let stored_reviews = ReviewRepository::find_by_ids(
+        db_pool,
+        &reviews
+            .iter()
+            .map(|(_, review)| review.id.clone())
+            .collect::<Vec<String>>(),
+    )
+    .await?;
+    let stored_commits = CommitRepository::find_by_ids(
+        db_pool,
+        &commits
+            .iter()
+            .map(|(_, commit)| commit.id.clone())
+            .collect::<Vec<String>>(),
+    )
+    .await?;
+
+    let mut diff = SyncDiff {
+        pull_requests: pull_requests
+            .iter()
+            .map(|pull_request| {
+                diff_row(
+                    &pull_request.id,
+                    format!("pull request {}", pull_request_label(pull_request)),
+                    pull_request_columns(pull_request),
+                    &stored_pull_requests,
+                )
+            })
+            .collect(),
+        reviews: reviews
+            .iter()
+            .map(|(pull_request, review)| {
+                diff_row(
+                    &review.id,
+                    format!(
+                        "review {} on {}",
+                        review.id,
+                        pull_request_label(pull_request)
+                    ),
+                    review_columns(pull_request, review),
+                    &stored_reviews,
+                )
+            })
+            .collect(),
+        commits: commits
+            .iter()
+            .map(|(pull_request, commit)| {
+                diff_row(
+                    &commit.id,
+                    format!(
+                        "commit {} on {}",
+                        commit.hash,
+                        pull_request_label(pull_request)
+                    ),
+                    commit_columns(pull_request, commit),
+                    &stored_commits,
+                )
+            })
+            .collect(),
+    };
+
+    diff.pull_requests.sort_by(|a, b| a.label.cmp(&b.label));
+    diff.reviews.sort_by(|a, b| a.label.cmp(&b.label));
+    diff.commits.sort_by(|a, b| a.label.cmp(&b.label));
+
+    Ok(diff)
+}
+
+fn diff_row(
+    id: &str,
+    label: String,
+    columns: StoredColumns,
+    stored: &HashMap<String, StoredColumns>,
+) -> RowDiff {
+    let (outcome, changes) = match stored.get(id) {
+        None => (
+            UpsertOutcome::Inserted,
+            columns
+                .into_iter()
+                .map(|(column, value)| ColumnChange {
+                    column,
+                    from: None,
+                    to: value,
+                })
+                .collect(),
+        ),
+        Some(stored_columns) => {
+            let stored_columns: HashMap<&str, &Option<String>> = stored_columns
+                .iter()
+                .map(|(column, value)| (*column, value))
+                .collect();
+            let changes: Vec<ColumnChange> = columns
+                .into_iter()
+                .filter_map(|(column, value)| {
+                    let from = (*stored_columns.get(column)?).clone();
+
+                    if from == value {
+                        None
+                    } else {
+                        Some(ColumnChange {
+                            column,
+                            from,
+                            to: value,
+                        })
+                    }
+                })
+                .collect();
+
+            if changes.is_empty() {
+                (UpsertOutcome::Unchanged, changes)
+            } else {
+                (UpsertOutcome::Updated, changes)
+            }
+        }
+    };
+
+    RowDiff {
+        id: id.to_string(),
+        label,
+        outcome,
+        changes,
+    }
+}
+
+fn pull_request_label(pull_request: &PullRequest) -> String {
+    format!("{}#{}", pull_request.repository, pull_request.number)
+}
+
+fn pull_request_columns(pull_request: &PullRequest) -> StoredColumns {
+    vec![
+        ("author_id", pull_request.author.id.clone()),
+        ("author_kind", text(Some(pull_request.author.kind.as_str()))),
+        ("author_login", pull_request.author.login.clone()),
+        ("repository", text(Some(&pull_request.repository))),
+        ("number", text(Some(pull_request.number))),
+        ("title", text(Some(&pull_request.title))),
+        ("date_opened", text(Some(pull_request.date_opened))),
+        ("date_closed", text(pull_request.date_closed)),
+        ("date_merged", text(pull_request.date_merged)),
+        ("merged", text(Some(pull_request.merged))),
+        ("additions", text(Some(pull_request.additions))),
+        ("deletions", text(Some(pull_request.deletions))),
+        ("changed_files", text(Some(pull_request.changed_files))),
+        ("base_branch", text(Some(&pull_request.base_branch))),
+        ("head_branch", text(Some(&pull_request.head_branch))),
+    ]
+}
+
+fn review_columns(pull_request: &PullRequest, review: &Review) -> StoredColumns {
+    vec![
+        ("date_published", text(Some(review.occurred_at))),
+        ("is_owner", text(Some(review.viewer_did_author))),
+        ("state", text(Some(review.state.as_str()))),
+        ("body", text(Some(&review.body))),
+        ("author_id", review.author.id.clone()),
+        ("author_kind", text(Some(review.author.kind.as_str()))),
+        ("author_login", review.author.login.clone()),
+        ("pull_request_id", text(Some(&pull_request.id))),
+    ]
+}
+
+fn commit_columns(pull_request: &PullRequest, commit: &Commit) -> StoredColumns {
+    vec![
+        ("hash", text(Some(&commit.hash))),
+        ("message_headline", text(Some(&commit.message_headline))),
+        ("date_committed", text(Some(commit.occurred_at))),
+        ("author_id", commit.author.id.clone()),
+        ("author_kind", text(Some(commit.author.kind.as_str()))),
+        ("author_login", commit.author.login.clone()),
+        ("author_name", commit.author.name.clone()),
+        ("author_email", commit.author.email.clone()),
+        ("pull_request_id", text(Some(&pull_request.id))),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn columns(values: &[(&'static str, Option<&str>)]) -> StoredColumns {
+        values
+            .iter()
+            .map(|(column, value)| (*column, value.map(String::from)))
+            .collect()
+    }
+
+    fn stored(values: &[(&'static str, Option<&str>)]) -> HashMap<String, StoredColumns> {
+        vec![("PR_1".to_string(), columns(values))]
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn rows_that_are_not_stored_are_inserted() {
+        let row = diff_row(
+            "PR_1",
+            "octo/repo#1".into(),
+            columns(&[("title", Some("Fix it")), ("date_merged", None)]),
+            &HashMap::new(),
+        );
+
+        assert_eq!(row.outcome, UpsertOutcome::Inserted);
+        assert_eq!(row.changes.len(), 2);
+        assert!(row.changes.iter().all(|change| change.from.is_none()));
+    }
+
+    #[test]
+    fn identical_rows_are_unchanged() {
+        let values = [("title", Some("Fix it")), ("date_merged", None)];
+        let row = diff_row(
+            "PR_1",
+            "octo/repo#1".into(),
+            columns(&values),
+            &stored(&values),
+        );
+
+        assert_eq!(row.outcome, UpsertOutcome::Unchanged);
+        assert!(row.changes.is_empty());
+    }
+
+    #[test]
+    fn lists_only_the_changed_columns_of_updated_rows() {
+        let row = diff_row(
+            "PR_1",
+            "octo/repo#1".into(),
+            columns(&[
+                ("title", Some("Fix it")),
+                ("date_merged", Some("2020-01-02T00:00:00+00:00")),
+            ]),
+            &stored(&[("title", Some("Fix it")), ("date_merged", None)]),
+        );
+
+        assert_eq!(row.outcome, UpsertOutcome::Updated);
+        assert_eq!(row.changes.len(), 1);
+        assert_eq!(row.changes[0].column, "date_merged");
+        assert_eq!(row.changes[0].from, None);
+        assert_eq!(
+            row.changes[0].to.as_deref(),
+            Some("2020-01-02T00:00:00+00:00")
+        );
+    }
+
+    #[test]
+    fn ignores_columns_present_on_one_side_only() {
+        let row = diff_row(
+            "PR_1",
+            "octo/repo#1".into(),
+            columns(&[("title", Some("Fix it")), ("head_branch", Some("fix"))]),
+            &stored(&[("title", Some("Fix it")), ("base_branch", Some("main"))]),
+        );
+
+        assert_eq!(row.outcome, UpsertOutcome::Unchanged);
+        assert!(row.changes.is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ContributionDay {
+    pub date: NaiveDate,
+    pub weekday: i16,
+    pub count: i32,
+    pub color: String,
+    pub level: i16,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct ContributionCalendarDTO {
+    pub days: Vec<ContributionDay>,
+}
+
+impl ContributionCalendarDTO {
+    pub fn new() -> ContributionCalendarDTO {
+        ContributionCalendarDTO::default()
+    }
+
+    pub fn add_day(
+        &mut self,
+        colors: &[String],
+        date: NaiveDate,
+        weekday: i64,
+        count: i64,
+        color: String,
+    ) {
+        let level = match colors.iter().position(|level_color| *level_color == color) {
+            Some(index) if count > 0 => index as i16 + 1,
+            _ => 0,
+        };
+
+        self.days.push(ContributionDay {
+            date,
+            weekday: weekday as i16,
+            count: count as i32,
+            color,
+            level,
+        });
+    }
+
+    pub fn total(&self) -> i64 {
+        self.days.iter().map(|day| day.count as i64).sum()
+    }
+}
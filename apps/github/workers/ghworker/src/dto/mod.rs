@@ -1,3 +1,7 @@
+pub mod contribution_calendar;
 pub mod pull_requests;
+pub mod sync_state;
 
-pub use pull_requests::PullRequestsDTO;
+pub use contribution_calendar::{ContributionCalendarDTO, ContributionDay};
+pub use pull_requests::{Author, AuthorKind, PullRequestsDTO, ReviewState};
+pub use sync_state::{ContributionType, SyncWindow};
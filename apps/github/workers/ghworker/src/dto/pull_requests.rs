@@ -1,108 +1,207 @@
-use std::collections::HashMap;
+use crate::dto::ContributionType;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReviewState {
+    Approved,
+    ChangesRequested,
+    Dismissed,
+    Commented,
+    Pending,
+}
+
+impl ReviewState {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ReviewState::Approved => "Approved",
+            ReviewState::ChangesRequested => "Changes requested",
+            ReviewState::Dismissed => "Dismissed",
+            ReviewState::Commented => "Commented",
+            ReviewState::Pending => "Pending",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ReviewState> {
+        match value {
+            "Approved" => Some(ReviewState::Approved),
+            "Changes requested" => Some(ReviewState::ChangesRequested),
+            "Dismissed" => Some(ReviewState::Dismissed),
+            "Commented" => Some(ReviewState::Commented),
+            "Pending" => Some(ReviewState::Pending),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuthorKind {
+    User,
+    Bot,
+    Mannequin,
+    Organization,
+    EnterpriseUserAccount,
+    Ghost,
+    Unlinked,
+}
+
+impl AuthorKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuthorKind::User => "user",
+            AuthorKind::Bot => "bot",
+            AuthorKind::Mannequin => "mannequin",
+            AuthorKind::Organization => "organization",
+            AuthorKind::EnterpriseUserAccount => "enterprise_user_account",
+            AuthorKind::Ghost => "ghost",
+            AuthorKind::Unlinked => "unlinked",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<AuthorKind> {
+        match value {
+            "user" => Some(AuthorKind::User),
+            "bot" => Some(AuthorKind::Bot),
+            "mannequin" => Some(AuthorKind::Mannequin),
+            "organization" => Some(AuthorKind::Organization),
+            "enterprise_user_account" => Some(AuthorKind::EnterpriseUserAccount),
+            "ghost" => Some(AuthorKind::Ghost),
+            "unlinked" => Some(AuthorKind::Unlinked),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Author {
+    pub kind: AuthorKind,
+    pub id: Option<String>,
+    pub login: Option<String>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+}
+
+impl Author {
+    pub fn new(kind: AuthorKind, id: String, login: String) -> Author {
+        Author {
+            kind,
+            id: Some(id),
+            login: Some(login),
+            name: None,
+            email: None,
+        }
+    }
+
+    pub fn ghost() -> Author {
+        Author {
+            kind: AuthorKind::Ghost,
+            id: None,
+            login: None,
+            name: None,
+            email: None,
+        }
+    }
+
+    pub fn unlinked() -> Author {
+        Author {
+            kind: AuthorKind::Unlinked,
+            id: None,
+            login: None,
+            name: None,
+            email: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Review {
     pub id: String,
-    pub occurred_at: String,
+    pub occurred_at: DateTime<Utc>,
     pub viewer_did_author: bool,
-    pub state: String,
-    pub author_id: String,
+    pub state: ReviewState,
+    pub body: String,
+    pub author: Author,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Commit {
     pub id: String,
     pub hash: String,
-    pub author_id: String,
-    pub occurred_at: String,
+    pub message_headline: String,
+    pub author: Author,
+    pub occurred_at: DateTime<Utc>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct PullRequest {
     pub id: String,
-    pub author_id: String,
-    pub date_opened: String,
-    pub date_closed: Option<String>,
+    pub author: Author,
+    pub title: String,
+    pub date_opened: DateTime<Utc>,
+    pub date_closed: Option<DateTime<Utc>>,
+    pub date_merged: Option<DateTime<Utc>>,
+    pub merged: bool,
     pub number: i64,
+    pub repository: String,
+    pub additions: i64,
+    pub deletions: i64,
+    pub changed_files: i64,
+    pub base_branch: String,
+    pub head_branch: String,
     pub reviews: Vec<Review>,
     pub commits: Vec<Commit>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct PullRequestsDTO {
+    pub user: Option<Author>,
     pub pull_requests: HashMap<String, PullRequest>,
+    pub contributions: HashSet<(String, ContributionType)>,
 }
 
 impl PullRequestsDTO {
     pub fn new() -> PullRequestsDTO {
-        PullRequestsDTO {
-            pull_requests: HashMap::new(),
-        }
+        PullRequestsDTO::default()
     }
 
-    pub fn add_pull_request(
-        &mut self,
-        id: String,
-        date_opened: String,
-        date_closed: Option<String>,
-        author_id: String,
-        number: i64,
-    ) {
-        self.pull_requests.insert(
-            id.clone(),
-            PullRequest {
-                id,
-                number,
-                author_id,
-                date_opened,
-                date_closed,
-                reviews: Vec::new(),
-                commits: Vec::new(),
-            },
-        );
+    pub fn add_pull_request(&mut self, pull_request: PullRequest) {
+        self.pull_requests
+            .insert(pull_request.id.clone(), pull_request);
     }
 
-    pub fn add_commit(
-        &mut self,
-        pull_request_id: &String,
-        commit_id: String,
-        occurred_at: String,
-        hash: String,
-        author_id: String,
-    ) {
-        match self.pull_requests.get_mut(pull_request_id) {
-            Some(pull_request) => {
-                pull_request.commits.push(Commit {
-                    id: commit_id,
-                    occurred_at,
-                    hash,
-                    author_id,
-                });
-            }
-            _ => {}
+    pub fn set_user(&mut self, id: String, login: String) {
+        self.user = Some(Author::new(AuthorKind::User, id, login));
+    }
+
+    pub fn add_contribution(&mut self, pull_request_id: &str, contribution_type: ContributionType) {
+        self.contributions
+            .insert((pull_request_id.to_string(), contribution_type));
+    }
+
+    pub fn add_commit(&mut self, pull_request_id: &str, commit: Commit) {
+        if let Some(pull_request) = self.pull_requests.get_mut(pull_request_id) {
+            pull_request.commits.push(commit);
+        }
+    }
+
+    pub fn merge(&mut self, other: PullRequestsDTO) {
+        if self.user.is_none() {
+            self.user = other.user;
         }
+
+        for (id, pull_request) in other.pull_requests {
+            self.pull_requests.entry(id).or_insert(pull_request);
+        }
+
+        self.contributions.extend(other.contributions);
     }
 
-    pub fn add_review(
-        &mut self,
-        pull_request_id: &String,
-        review_id: String,
-        occurred_at: String,
-        viewer_did_author: bool,
-        state: String,
-        author_id: String,
-    ) {
-        match self.pull_requests.get_mut(pull_request_id) {
-            Some(pull_request) => {
-                pull_request.reviews.push(Review {
-                    id: review_id,
-                    occurred_at,
-                    viewer_did_author,
-                    state,
-                    author_id,
-                });
-            }
-            _ => {}
+    pub fn add_review(&mut self, pull_request_id: &str, review: Review) {
+        if let Some(pull_request) = self.pull_requests.get_mut(pull_request_id) {
+            pull_request.reviews.push(review);
         }
     }
 }
@@ -0,0 +1,174 @@
+use chrono::{DateTime, Duration, NaiveDate, SubsecRound, TimeZone, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContributionType {
+    PullRequests,
+    PullRequestReviews,
+    Backfill,
+}
+
+impl ContributionType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ContributionType::PullRequests => "pull_requests",
+            ContributionType::PullRequestReviews => "pull_request_reviews",
+            ContributionType::Backfill => "backfill",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<ContributionType> {
+        match value {
+            "pull_requests" => Some(ContributionType::PullRequests),
+            "pull_request_reviews" => Some(ContributionType::PullRequestReviews),
+            "backfill" => Some(ContributionType::Backfill),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncWindow {
+    pub from: Option<DateTime<Utc>>,
+    pub to: DateTime<Utc>,
+}
+
+impl SyncWindow {
+    pub fn since(last_synced_at: Option<DateTime<Utc>>, to: DateTime<Utc>) -> Vec<SyncWindow> {
+        let mut from = match last_synced_at {
+            Some(from) => from,
+            None => return vec![SyncWindow { from: None, to }],
+        };
+        let mut windows = Vec::new();
+
+        while from + Duration::days(365) < to {
+            windows.push(SyncWindow {
+                from: Some(from),
+                to: from + Duration::days(365),
+            });
+            from += Duration::days(365);
+        }
+        windows.push(SyncWindow {
+            from: Some(from),
+            to,
+        });
+
+        windows
+    }
+
+    pub fn backfill(years: &[i64], now: DateTime<Utc>) -> Vec<SyncWindow> {
+        let mut windows = Vec::new();
+
+        for year in years {
+            for month in 1..=12 {
+                let from = match month_start(*year as i32, month) {
+                    Some(from) if from < now => from,
+                    _ => break,
+                };
+                let to = match month {
+                    12 => month_start(*year as i32 + 1, 1),
+                    _ => month_start(*year as i32, month + 1),
+                };
+
+                windows.push(SyncWindow {
+                    from: Some(from),
+                    to: to.map_or(now, |to| to.min(now)),
+                });
+            }
+        }
+
+        windows
+    }
+
+    pub fn span(&self, other: &SyncWindow) -> SyncWindow {
+        SyncWindow {
+            from: match (self.from, other.from) {
+                (Some(from), Some(other_from)) => Some(from.min(other_from)),
+                _ => None,
+            },
+            to: self.to.max(other.to),
+        }
+    }
+
+    pub fn from_variable(&self) -> Option<DateTime<Utc>> {
+        self.from.map(|from| from.trunc_subsecs(0))
+    }
+
+    pub fn to_variable(&self) -> Option<DateTime<Utc>> {
+        Some(self.to.trunc_subsecs(0))
+    }
+}
+
+fn month_start(year: i32, month: u32) -> Option<DateTime<Utc>> {
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|date| Utc.from_utc_datetime(&date))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(year: i32, month: u32, day: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(year, month, day, 0, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn syncs_everything_github_allows_without_a_previous_sync() {
+        let windows = SyncWindow::since(None, date(2020, 6, 1));
+
+        assert_eq!(windows.len(), 1);
+        assert_eq!(windows[0].from, None);
+        assert_eq!(windows[0].to, date(2020, 6, 1));
+    }
+
+    #[test]
+    fn splits_old_syncs_into_windows_of_a_year() {
+        let windows = SyncWindow::since(Some(date(2018, 1, 1)), date(2020, 6, 1));
+        let bounds: Vec<_> = windows
+            .iter()
+            .map(|window| (window.from.unwrap(), window.to))
+            .collect();
+
+        assert_eq!(
+            bounds,
+            vec![
+                (date(2018, 1, 1), date(2019, 1, 1)),
+                (date(2019, 1, 1), date(2020, 1, 1)),
+                (date(2020, 1, 1), date(2020, 6, 1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn backfills_a_past_year_month_by_month() {
+        let windows = SyncWindow::backfill(&[2019], date(2020, 6, 1));
+
+        assert_eq!(windows.len(), 12);
+        assert_eq!(windows[0].from, Some(date(2019, 1, 1)));
+        assert_eq!(windows[0].to, date(2019, 2, 1));
+        assert!(windows
+            .windows(2)
+            .all(|pair| pair[0].to == pair[1].from.unwrap()));
+    }
+
+    #[test]
+    fn rolls_december_over_into_the_next_year() {
+        let windows = SyncWindow::backfill(&[2019], date(2020, 6, 1));
+
+        assert_eq!(windows[11].from, Some(date(2019, 12, 1)));
+        assert_eq!(windows[11].to, date(2020, 1, 1));
+    }
+
+    #[test]
+    fn stops_backfilling_at_now() {
+        let now = Utc.with_ymd_and_hms(2020, 3, 15, 12, 0, 0).unwrap();
+        let windows = SyncWindow::backfill(&[2020, 2019], now);
+
+        assert_eq!(windows.len(), 3 + 12);
+        assert_eq!(windows[2].from, Some(date(2020, 3, 1)));
+        assert_eq!(windows[2].to, now);
+        assert_eq!(windows[3].from, Some(date(2019, 1, 1)));
+    }
+}
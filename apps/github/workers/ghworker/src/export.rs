@@ -0,0 +1,287 @@
+use crate::dto::pull_requests::{Author, PullRequest, PullRequestsDTO};
+use crate::dto::ContributionType;
+use crate::store::{unique_commits, unique_reviews};
+use anyhow::*;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    Ndjson,
+}
+
+impl FromStr for ExportFormat {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<ExportFormat> {
+        match value {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            _ => Err(anyhow!("Unknown export format {}", value)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportSource {
+    Database,
+    Github,
+}
+
+impl FromStr for ExportSource {
+    type Err = Error;
+
+    fn from_str(value: &str) -> Result<ExportSource> {
+        match value {
+            "database" => Ok(ExportSource::Database),
+            "github" => Ok(ExportSource::Github),
+            _ => Err(anyhow!("Unknown export source {}", value)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PullRequestRow<'a> {
+    id: &'a str,
+    repository: &'a str,
+    number: i64,
+    title: &'a str,
+    author_kind: &'static str,
+    author_id: Option<&'a str>,
+    author_login: Option<&'a str>,
+    date_opened: DateTime<Utc>,
+    date_closed: Option<DateTime<Utc>>,
+    date_merged: Option<DateTime<Utc>>,
+    merged: bool,
+    additions: i64,
+    deletions: i64,
+    changed_files: i64,
+    base_branch: &'a str,
+    head_branch: &'a str,
+    contribution_types: String,
+}
+
+#[derive(Serialize)]
+struct ReviewRow<'a> {
+    id: &'a str,
+    pull_request_id: &'a str,
+    repository: &'a str,
+    number: i64,
+    date_published: DateTime<Utc>,
+    is_owner: bool,
+    state: &'static str,
+    body: &'a str,
+    author_kind: &'static str,
+    author_id: Option<&'a str>,
+    author_login: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct CommitRow<'a> {
+    id: &'a str,
+    pull_request_id: &'a str,
+    repository: &'a str,
+    number: i64,
+    hash: &'a str,
+    message_headline: &'a str,
+    date_committed: DateTime<Utc>,
+    author_kind: &'static str,
+    author_id: Option<&'a str>,
+    author_login: Option<&'a str>,
+    author_name: Option<&'a str>,
+    author_email: Option<&'a str>,
+}
+
+#[derive(Serialize)]
+struct NestedPullRequest<'a> {
+    #[serde(flatten)]
+    pull_request: &'a PullRequest,
+    contribution_types: Vec<ContributionType>,
+}
+
+#[derive(Serialize)]
+struct NestedExport<'a> {
+    username: &'a str,
+    exported_at: DateTime<Utc>,
+    user: Option<&'a Author>,
+    pull_requests: Vec<NestedPullRequest<'a>>,
+}
+
+pub fn export_pull_requests_dto(
+    pull_requests_dto: &PullRequestsDTO,
+    username: &str,
+    format: ExportFormat,
+    out: &Path,
+) -> Result<Vec<PathBuf>> {
+    fs::create_dir_all(out).context(format!("Creating the export directory {}", out.display()))?;
+
+    let mut pull_requests: Vec<&PullRequest> = pull_requests_dto.pull_requests.values().collect();
+    pull_requests
+        .sort_by(|a, b| (&a.repository, a.number, &a.id).cmp(&(&b.repository, b.number, &b.id)));
+
+    let files = match format {
+        ExportFormat::Json => {
+            let nested = NestedExport {
+                username,
+                exported_at: Utc::now(),
+                user: pull_requests_dto.user.as_ref(),
+                pull_requests: pull_requests
+                    .iter()
+                    .map(|pull_request| NestedPullRequest {
+                        pull_request,
+                        contribution_types: contribution_types(pull_requests_dto, pull_request),
+                    })
+                    .collect(),
+            };
+            let path = out.join("pull_requests.json");
+            let mut writer = create(&path)?;
+            serde_json::to_writer_pretty(&mut writer, &nested)?;
+            writer.flush()?;
+
+            vec![path]
+        }
+        ExportFormat::Csv | ExportFormat::Ndjson => {
+            let extension = if format == ExportFormat::Csv {
+                "csv"
+            } else {
+                "ndjson"
+            };
+            let pull_request_rows: Vec<PullRequestRow> = pull_requests
+                .iter()
+                .map(|pull_request| pull_request_row(pull_requests_dto, pull_request))
+                .collect();
+            let review_rows: Vec<ReviewRow> = unique_reviews(&pull_requests)
+                .into_iter()
+                .map(|(pull_request, review)| ReviewRow {
+                    id: &review.id,
+                    pull_request_id: &pull_request.id,
+                    repository: &pull_request.repository,
+                    number: pull_request.number,
+                    date_published: review.occurred_at,
+                    is_owner: review.viewer_did_author,
+                    state: review.state.as_str(),
+                    body: &review.body,
+                    author_kind: review.author.kind.as_str(),
+                    author_id: review.author.id.as_deref(),
+                    author_login: review.author.login.as_deref(),
+                })
+                .collect();
+            let commit_rows: Vec<CommitRow> = unique_commits(&pull_requests)
+                .into_iter()
+                .map(|(pull_request, commit)| CommitRow {
+                    id: &commit.id,
+                    pull_request_id: &pull_request.id,
+                    repository: &pull_request.repository,
+                    number: pull_request.number,
+                    hash: &commit.hash,
+                    message_headline: &commit.message_headline,
+                    date_committed: commit.occurred_at,
+                    author_kind: commit.author.kind.as_str(),
+                    author_id: commit.author.id.as_deref(),
+                    author_login: commit.author.login.as_deref(),
+                    author_name: commit.author.name.as_deref(),
+                    author_email: commit.author.email.as_deref(),
+                })
+                .collect();
+
+            vec![
+                write_rows(out, "pull_requests", extension, &pull_request_rows)?,
+                write_rows(out, "reviews", extension, &review_rows)?,
+                write_rows(out, "commits", extension, &commit_rows)?,
+            ]
+        }
+    };
+
+    info!(
+        "Exported {} pull requests for {} to {}",
+        pull_requests.len(),
+        username,
+        out.display()
+    );
+
+    Ok(files)
+}
+
+fn contribution_types(
+    pull_requests_dto: &PullRequestsDTO,
+    pull_request: &PullRequest,
+) -> Vec<ContributionType> {
+    let mut contribution_types: Vec<ContributionType> = pull_requests_dto
+        .contributions
+        .iter()
+        .filter(|(pull_request_id, _)| *pull_request_id == pull_request.id)
+        .map(|(_, contribution_type)| *contribution_type)
+        .collect();
+    contribution_types.sort_by_key(|contribution_type| contribution_type.as_str());
+
+    contribution_types
+}
+
+fn pull_request_row<'a>(
+    pull_requests_dto: &PullRequestsDTO,
+    pull_request: &'a PullRequest,
+) -> PullRequestRow<'a> {
+    PullRequestRow {
+        id: &pull_request.id,
+        repository: &pull_request.repository,
+        number: pull_request.number,
+        title: &pull_request.title,
+        author_kind: pull_request.author.kind.as_str(),
+        author_id: pull_request.author.id.as_deref(),
+        author_login: pull_request.author.login.as_deref(),
+        date_opened: pull_request.date_opened,
+        date_closed: pull_request.date_closed,
+        date_merged: pull_request.date_merged,
+        merged: pull_request.merged,
+        additions: pull_request.additions,
+        deletions: pull_request.deletions,
+        changed_files: pull_request.changed_files,
+        base_branch: &pull_request.base_branch,
+        head_branch: &pull_request.head_branch,
+        contribution_types: contribution_types(pull_requests_dto, pull_request)
+            .iter()
+            .map(|contribution_type| contribution_type.as_str())
+            .collect::<Vec<&str>>()
+            .join(";"),
+    }
+}
+
+fn write_rows<T: Serialize>(
+    out: &Path,
+    name: &str,
+    extension: &str,
+    rows: &[T],
+) -> Result<PathBuf> {
+    let path = out.join(format!("{}.{}", name, extension));
+
+    if extension == "csv" {
+        let mut writer = csv::Writer::from_writer(create(&path)?);
+        for row in rows {
+            writer.serialize(row)?;
+        }
+        writer.flush()?;
+    } else {
+        let mut writer = create(&path)?;
+        for row in rows {
+            serde_json::to_writer(&mut writer, row)?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+    }
+
+    Ok(path)
+}
+
+fn create(path: &Path) -> Result<BufWriter<File>> {
+    let file = File::create(path).context(format!("Creating {}", path.display()))?;
+
+    Ok(BufWriter::new(file))
+}
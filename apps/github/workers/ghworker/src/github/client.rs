@@ -0,0 +1,300 @@
+use crate::github::error::{GithubError, GraphQLError};
+use crate::github::rate_limit::{retry_after, RateLimit, RateLimiter, MIN_PAUSE};
+use crate::github::retry::RetryPolicy;
+use crate::github::snapshot::{Recorder, Replayer};
+use anyhow::*;
+use async_lock::Semaphore;
+use async_std::task;
+use chrono::Utc;
+use graphql_client::{GraphQLQuery, QueryBody};
+use isahc::config::Configurable;
+use isahc::http::header::{HeaderMap, AUTHORIZATION, CONTENT_TYPE, USER_AGENT};
+use isahc::http::StatusCode;
+use isahc::{AsyncReadResponseExt, HttpClient, Request};
+use log::{debug, warn};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct GithubOptions {
+    #[structopt(
+        long = "github-endpoint",
+        env = "GITHUB_GRAPHQL_URL",
+        default_value = "https://api.github.com/graphql"
+    )]
+    pub endpoint: String,
+    #[structopt(
+        long = "github-user-agent",
+        env = "GITHUB_USER_AGENT",
+        default_value = "mystory-ghworker"
+    )]
+    pub user_agent: String,
+    #[structopt(
+        long = "github-timeout",
+        env = "GITHUB_TIMEOUT_SECS",
+        default_value = "30"
+    )]
+    pub timeout: u64,
+    #[structopt(
+        long = "github-connect-timeout",
+        env = "GITHUB_CONNECT_TIMEOUT_SECS",
+        default_value = "10"
+    )]
+    pub connect_timeout: u64,
+    #[structopt(
+        long = "github-concurrency",
+        env = "GITHUB_CONCURRENCY",
+        default_value = "4"
+    )]
+    pub concurrency: usize,
+    #[structopt(
+        long = "github-record",
+        env = "GITHUB_RECORD_PATH",
+        parse(from_os_str),
+        conflicts_with = "replay"
+    )]
+    pub record: Option<PathBuf>,
+    #[structopt(long = "github-replay", env = "GITHUB_REPLAY_PATH", parse(from_os_str))]
+    pub replay: Option<PathBuf>,
+    #[structopt(flatten)]
+    pub retry: RetryPolicy,
+}
+
+#[derive(Deserialize, Debug)]
+struct GraphQLResponse<T> {
+    data: Option<T>,
+    errors: Option<Vec<GraphQLError>>,
+}
+
+pub struct GithubApi {
+    http: HttpClient,
+    options: GithubOptions,
+    rate_limiters: Mutex<HashMap<String, Arc<RateLimiter>>>,
+    recorder: Option<Arc<Recorder>>,
+    replayer: Option<Arc<Replayer>>,
+}
+
+impl GithubApi {
+    pub fn new(options: &GithubOptions) -> Result<GithubApi> {
+        let http = HttpClient::builder()
+            .default_header(USER_AGENT, options.user_agent.as_str())
+            .timeout(Duration::from_secs(options.timeout))
+            .connect_timeout(Duration::from_secs(options.connect_timeout))
+            .automatic_decompression(true)
+            .build()
+            .map_err(GithubError::from)
+            .context("Building the github http client")?;
+
+        Ok(GithubApi {
+            http,
+            options: options.clone(),
+            rate_limiters: Mutex::new(HashMap::new()),
+            recorder: options
+                .record
+                .as_deref()
+                .map(Recorder::new)
+                .transpose()?
+                .map(Arc::new),
+            replayer: options
+                .replay
+                .as_deref()
+                .map(Replayer::load)
+                .transpose()?
+                .map(Arc::new),
+        })
+    }
+
+    pub fn client(&self, token: String) -> GithubClient {
+        let rate_limiter = {
+            let mut rate_limiters = self.rate_limiters.lock().unwrap();
+            rate_limiters.retain(|_, rate_limiter| {
+                Arc::strong_count(rate_limiter) > 1
+                    || rate_limiter
+                        .current()
+                        .is_some_and(|rate_limit| rate_limit.reset_at > Utc::now())
+            });
+            rate_limiters
+                .entry(token.clone())
+                .or_insert_with(|| Arc::new(RateLimiter::new()))
+                .clone()
+        };
+        let concurrency = self.options.concurrency.max(1);
+
+        GithubClient {
+            http: self.http.clone(),
+            token,
+            endpoint: self.options.endpoint.clone(),
+            rate_limiter,
+            retry_policy: self.options.retry.clone(),
+            concurrency,
+            requests: Semaphore::new(concurrency),
+            recorder: self.recorder.clone(),
+            replayer: self.replayer.clone(),
+        }
+    }
+}
+
+pub struct GithubClient {
+    http: HttpClient,
+    token: String,
+    endpoint: String,
+    rate_limiter: Arc<RateLimiter>,
+    retry_policy: RetryPolicy,
+    concurrency: usize,
+    requests: Semaphore,
+    recorder: Option<Arc<Recorder>>,
+    replayer: Option<Arc<Replayer>>,
+}
+
+impl GithubClient {
+    pub fn new(options: &GithubOptions, token: String) -> Result<GithubClient> {
+        Ok(GithubApi::new(options)?.client(token))
+    }
+
+    pub fn concurrency(&self) -> usize {
+        self.concurrency
+    }
+
+    pub fn rate_limit(&self) -> Option<RateLimit> {
+        self.rate_limiter.current()
+    }
+
+    pub async fn query<Q: GraphQLQuery>(&self, variables: Q::Variables) -> Result<Q::ResponseData> {
+        let request_body = Q::build_query(variables);
+        let mut attempt = 1;
+
+        loop {
+            match self.execute::<Q>(&request_body).await {
+                Err(err) if self.retry_policy.should_retry(attempt, &err) => {
+                    let delay = match err.downcast_ref::<GithubError>() {
+                        Some(GithubError::RateLimited { retry_after }) => {
+                            retry_after.unwrap_or_default().max(MIN_PAUSE)
+                        }
+                        _ => self.retry_policy.delay(attempt),
+                    };
+                    warn!(
+                        "Attempt {} of {} for {} failed, retrying in {}ms: {}",
+                        attempt,
+                        self.retry_policy.max_attempts,
+                        request_body.operation_name,
+                        delay.as_millis(),
+                        err
+                    );
+                    task::sleep(delay).await;
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+
+    async fn execute<Q: GraphQLQuery>(
+        &self,
+        request_body: &QueryBody<Q::Variables>,
+    ) -> Result<Q::ResponseData> {
+        self.rate_limiter.wait_for_budget().await;
+        debug!(
+            "Sending the {} query to {}",
+            request_body.operation_name, self.endpoint
+        );
+
+        let (status, headers, body) = self.send(request_body).await?;
+
+        self.rate_limiter.record_headers(&headers);
+
+        if status == StatusCode::FORBIDDEN || status == StatusCode::TOO_MANY_REQUESTS {
+            if let Some(pause) = retry_after(&headers) {
+                return Err(GithubError::RateLimited {
+                    retry_after: Some(pause),
+                }
+                .into());
+            }
+
+            if let Some(RateLimit { remaining: 0, .. }) = self.rate_limiter.current() {
+                return Err(GithubError::RateLimited { retry_after: None }.into());
+            }
+        }
+
+        if !status.is_success() {
+            return Err(GithubError::Http {
+                status: status.as_u16(),
+                body,
+            }
+            .into());
+        }
+
+        let response: Value =
+            serde_json::from_str(&body).context("Attempting to parse the response body")?;
+        self.rate_limiter.record_response(&response);
+        let data = response["data"].clone();
+
+        let response: GraphQLResponse<Q::ResponseData> = serde_json::from_value(response)
+            .context("Attempting to deserialize the response object")?;
+        let errors = response.errors.unwrap_or_default();
+
+        if errors.iter().any(GraphQLError::is_rate_limited) {
+            self.rate_limiter.mark_exhausted();
+            return Err(GithubError::RateLimited { retry_after: None }.into());
+        }
+
+        match response.data {
+            Some(response_data) if errors.iter().all(|err| err.is_partial(&data)) => {
+                for err in &errors {
+                    warn!(
+                        "Ignoring a github error on part of the {} response: {}",
+                        request_body.operation_name, err
+                    );
+                }
+                Ok(response_data)
+            }
+            _ if !errors.is_empty() => Err(GithubError::GraphQL(errors).into()),
+            data => Ok(data.ok_or(GithubError::MissingData)?),
+        }
+    }
+
+    async fn send<V: serde::Serialize>(
+        &self,
+        request_body: &QueryBody<V>,
+    ) -> Result<(StatusCode, HeaderMap, String)> {
+        let variables = serde_json::to_value(&request_body.variables)?;
+
+        if let Some(replayer) = &self.replayer {
+            let (status, body) = replayer.replay(request_body.operation_name, &variables)?;
+            return Ok((status, HeaderMap::new(), body));
+        }
+
+        let body = serde_json::to_vec(request_body).context("Serializing the github query")?;
+        let request = Request::post(&self.endpoint)
+            .header(AUTHORIZATION, format!("Bearer {}", self.token))
+            .header(CONTENT_TYPE, "application/json")
+            .body(body)
+            .context("Building the github request")?;
+        let _permit = self.requests.acquire().await;
+
+        let mut raw_response = self
+            .http
+            .send_async(request)
+            .await
+            .map_err(GithubError::from)?;
+        let body = raw_response
+            .text()
+            .await
+            .map_err(|e| GithubError::from(isahc::Error::from(e)))?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record(
+                request_body.operation_name,
+                variables,
+                raw_response.status(),
+                &body,
+            )?;
+        }
+
+        Ok((raw_response.status(), raw_response.headers().clone(), body))
+    }
+}
@@ -0,0 +1,49 @@
+use crate::dto::ContributionCalendarDTO;
+use crate::github::client::GithubClient;
+use crate::github::queries::{contributions_query, ContributionsQuery};
+use anyhow::*;
+use log::debug;
+
+pub async fn get_contribution_calendar(
+    client: &GithubClient,
+    username: String,
+) -> Result<ContributionCalendarDTO> {
+    debug!("Fetching the contribution calendar for {}", username);
+
+    let response = client
+        .query::<ContributionsQuery>(contributions_query::Variables {
+            username: username.clone(),
+        })
+        .await
+        .context(format!(
+            "Retrieving the contribution calendar for {}",
+            username
+        ))?;
+
+    let user = response
+        .user
+        .context(format!("The github user {} does not exist", username))?;
+    let calendar = user.contributions_collection.contribution_calendar;
+    let mut contribution_calendar = ContributionCalendarDTO::new();
+
+    for week in calendar.weeks {
+        for day in week.contribution_days {
+            contribution_calendar.add_day(
+                &calendar.colors,
+                day.date,
+                day.weekday,
+                day.contribution_count,
+                day.color,
+            );
+        }
+    }
+
+    debug!(
+        "Fetched {} contribution days for {} with {} contributions",
+        contribution_calendar.days.len(),
+        username,
+        contribution_calendar.total()
+    );
+
+    Ok(contribution_calendar)
+}
@@ -0,0 +1,26 @@
+use crate::github::client::GithubClient;
+use crate::github::queries::{contribution_years_query, ContributionYearsQuery};
+use anyhow::*;
+use log::debug;
+
+pub async fn get_contribution_years(client: &GithubClient, username: String) -> Result<Vec<i64>> {
+    debug!("Fetching the contribution years for {}", username);
+
+    let response = client
+        .query::<ContributionYearsQuery>(contribution_years_query::Variables {
+            username: username.clone(),
+        })
+        .await
+        .context(format!(
+            "Retrieving the contribution years for {}",
+            username
+        ))?;
+
+    let user = response
+        .user
+        .context(format!("The github user {} does not exist", username))?;
+    let mut years = user.contributions_collection.contribution_years;
+    years.sort();
+
+    Ok(years)
+}
@@ -0,0 +1,109 @@
+use serde::Deserialize;
+use std::fmt;
+use std::time::Duration;
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct GraphQLError {
+    pub message: String,
+    #[serde(rename = "type")]
+    pub error_type: Option<String>,
+    pub path: Option<Vec<serde_json::Value>>,
+    pub locations: Option<Vec<serde_json::Value>>,
+    pub extensions: Option<serde_json::Value>,
+}
+
+impl GraphQLError {
+    pub fn is_rate_limited(&self) -> bool {
+        self.error_type.as_deref() == Some("RATE_LIMITED")
+    }
+
+    pub fn is_transient(&self) -> bool {
+        self.error_type.is_none() && self.locations.is_none() && self.extensions.is_none()
+    }
+
+    pub fn is_partial(&self, data: &serde_json::Value) -> bool {
+        match self.path.as_deref() {
+            Some([field, _, ..]) => field.as_str().is_some_and(|field| !data[field].is_null()),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for GraphQLError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.error_type {
+            Some(error_type) => write!(f, "[{}] {}", error_type, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GithubError {
+    Transport(isahc::Error),
+    Http { status: u16, body: String },
+    GraphQL(Vec<GraphQLError>),
+    RateLimited { retry_after: Option<Duration> },
+    MissingData,
+}
+
+impl GithubError {
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GithubError::Transport(err) => !err.is_client(),
+            GithubError::Http { status, body } => match *status {
+                401 | 404 => false,
+                403 => body.contains("abuse") || body.contains("secondary rate limit"),
+                408 | 429 => true,
+                status => status >= 500,
+            },
+            GithubError::GraphQL(errors) => errors.iter().all(GraphQLError::is_transient),
+            GithubError::RateLimited { .. } => true,
+            GithubError::MissingData => false,
+        }
+    }
+}
+
+impl fmt::Display for GithubError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GithubError::Transport(err) => write!(f, "Could not reach the github API: {}", err),
+            GithubError::Http { status, body } => {
+                write!(f, "The github API responded with {}: {}", status, body)
+            }
+            GithubError::GraphQL(errors) => {
+                write!(f, "The github API returned {} error(s)", errors.len())?;
+                for err in errors {
+                    write!(f, "; {}", err)?;
+                }
+                Ok(())
+            }
+            GithubError::RateLimited {
+                retry_after: Some(retry_after),
+            } => write!(
+                f,
+                "Hit a github secondary rate limit, retry after {}s",
+                retry_after.as_secs()
+            ),
+            GithubError::RateLimited { retry_after: None } => {
+                write!(f, "The github rate limit was exhausted")
+            }
+            GithubError::MissingData => write!(f, "The github API returned no data"),
+        }
+    }
+}
+
+impl std::error::Error for GithubError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            GithubError::Transport(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<isahc::Error> for GithubError {
+    fn from(err: isahc::Error) -> GithubError {
+        GithubError::Transport(err)
+    }
+}
@@ -1,7 +1,22 @@
+pub mod client;
 pub mod commits;
+pub mod contribution_calendar;
+pub mod contribution_years;
+pub mod error;
 pub mod pull_request;
 pub mod pull_request_reviews;
+pub mod pull_requests_by_id;
+pub mod queries;
+pub mod rate_limit;
+pub mod retry;
 pub mod reviews;
+pub mod snapshot;
+pub mod sub_resources;
 
+pub use client::{GithubApi, GithubClient, GithubOptions};
+pub use contribution_calendar::get_contribution_calendar;
+pub use contribution_years::get_contribution_years;
+pub use error::GithubError;
 pub use pull_request::get_pull_request_contributions;
 pub use pull_request_reviews::get_pull_request_review_contributions;
+pub use pull_requests_by_id::get_pull_requests_by_id;
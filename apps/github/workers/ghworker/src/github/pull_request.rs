@@ -1,159 +1,126 @@
-use crate::dto::PullRequestsDTO;
-use crate::github::commits::fetch_pull_request_commits;
-use crate::github::reviews::fetch_pull_request_reviews;
+use crate::dto::pull_requests::{Commit, PullRequest, Review};
+use crate::dto::{ContributionType, PullRequestsDTO, SyncWindow};
+use crate::github::client::GithubClient;
+use crate::github::queries::{pull_request_contributions_query, PullRequestContributionsQuery};
+use crate::github::sub_resources::{fetch_pending, PendingFetch, SubResource};
 use anyhow::*;
-use graphql_client::GraphQLQuery;
-use graphql_client::Response;
-use log::{debug, error};
-
-type DateTime = String;
-
-#[derive(GraphQLQuery)]
-#[graphql(
-    schema_path = "schemas/github_schema.graphql",
-    query_path = "schemas/queries.graphql",
-    response_derives = "Debug"
-)]
-struct PullRequestContributionsQuery;
+use log::debug;
 
 pub async fn fetch_pull_requests(
-    token: String,
+    client: &GithubClient,
     username: String,
     current_cursor: String,
+    window: &SyncWindow,
 ) -> Result<pull_request_contributions_query::ResponseData> {
-    let request_body =
-        PullRequestContributionsQuery::build_query(pull_request_contributions_query::Variables {
+    client
+        .query::<PullRequestContributionsQuery>(pull_request_contributions_query::Variables {
             username: username.clone(),
             current_cursor: Some(current_cursor.clone()),
-        });
-    let mut raw_response = reqwest::Client::new()
-        .post("https://api.github.com/graphql")
-        .bearer_auth(token)
-        .json(&request_body)
-        .send()?;
-
-    let response: Response<pull_request_contributions_query::ResponseData> = raw_response
-        .json()
-        .context("Attempting to deserialize the response object")?;
-
-    if let Some(errors) = response.errors {
-        error!("Got errors from querying the github API for contributions");
-
-        for err in errors {
-            error!("{:#?}", err);
-        }
-    }
-
-    Ok(response
-        .data
-        .context("Retrieving the pull request contribution's response data")?)
+            from: window.from_variable(),
+            to: window.to_variable(),
+        })
+        .await
+        .context("Retrieving the pull request contribution's response data")
 }
 
-pub async fn get_pull_request_contributions(
-    token: String,
+pub async fn get_pull_request_contributions<'a>(
+    client: &GithubClient,
     username: String,
-    mut pull_request_dto: &mut PullRequestsDTO,
-) -> Result<&PullRequestsDTO> {
+    window: &SyncWindow,
+    pull_request_dto: &'a mut PullRequestsDTO,
+) -> Result<&'a PullRequestsDTO> {
     let mut current_cursor: String = String::from("");
+    let mut pending = Vec::new();
 
     loop {
         debug!("Taking the next 100 pull request contributions...");
         let pull_request_contributions_data =
-            fetch_pull_requests(token.clone(), username.clone(), current_cursor.clone()).await?;
+            fetch_pull_requests(client, username.clone(), current_cursor.clone(), window).await?;
 
         if let Some(user) = pull_request_contributions_data.user {
+            pull_request_dto.set_user(user.id.clone(), user.login.clone());
+
             if let Some(nodes) = user
                 .contributions_collection
                 .pull_request_contributions
                 .nodes
             {
-                for contribution in nodes {
-                    if let Some(contribution) = contribution {
-                        if let pull_request_contributions_query::PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestAuthorOn::User(author) = contribution.pull_request.author.unwrap().on {
-
-                            pull_request_dto.add_pull_request(
-                                contribution.pull_request.id.clone(),
-                                contribution.pull_request.created_at,
-                                contribution.pull_request.closed_at,
-                                author.id,
-                                contribution.pull_request.number.clone(),
-                            );
-
-                        }
-
-                        let repository = contribution.pull_request.repository;
-
-                        if let Some(reviews) = contribution.pull_request.reviews {
-                            if reviews.page_info.has_next_page {
-                                fetch_pull_request_reviews(
-                                    repository.name_with_owner.clone(),
-                                    contribution.pull_request.number.clone(),
-                                    &contribution.pull_request.id.clone(),
-                                    token.clone(),
-                                    &mut pull_request_dto,
-                                )
-                                .await?;
-                            } else {
-                                for review_collection in reviews.nodes {
-                                    for review in review_collection {
-                                        if let Some(review) = review {
-                                            let review_state: String = String::from(match review.state {
-                                                    pull_request_contributions_query::PullRequestReviewState::APPROVED => "Approved",
-                                                    pull_request_contributions_query::PullRequestReviewState::CHANGES_REQUESTED => "Changes requested",
-                                                    pull_request_contributions_query::PullRequestReviewState::DISMISSED => "Dismissed",
-                                                    pull_request_contributions_query::PullRequestReviewState::COMMENTED => "Commented",
-                                                    _ => "Pending",
-                                                });
-
-                                            if let Some(published_at) = review.published_at {
-                                                if let pull_request_contributions_query::PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestReviewsNodesAuthorOn::User(author) = review.author.unwrap().on {
-                                                        pull_request_dto.add_review(
-                                                            &contribution.pull_request.id,
-                                                            review.id,
-                                                            published_at,
-                                                            review.viewer_did_author,
-                                                            review_state,
-                                                            author.id,
-                                                        );
-                                                    }
-                                            }
-                                        }
+                for contribution in nodes.into_iter().flatten() {
+                    pull_request_dto.add_pull_request(PullRequest {
+                        id: contribution.pull_request.id.clone(),
+                        author: contribution.pull_request.author.into(),
+                        title: contribution.pull_request.title.clone(),
+                        date_opened: contribution.pull_request.created_at,
+                        date_closed: contribution.pull_request.closed_at,
+                        date_merged: contribution.pull_request.merged_at,
+                        merged: contribution.pull_request.merged,
+                        number: contribution.pull_request.number,
+                        repository: contribution.pull_request.repository.name_with_owner.clone(),
+                        additions: contribution.pull_request.additions,
+                        deletions: contribution.pull_request.deletions,
+                        changed_files: contribution.pull_request.changed_files,
+                        base_branch: contribution.pull_request.base_ref_name.clone(),
+                        head_branch: contribution.pull_request.head_ref_name.clone(),
+                        reviews: Vec::new(),
+                        commits: Vec::new(),
+                    });
+                    pull_request_dto.add_contribution(
+                        &contribution.pull_request.id,
+                        ContributionType::PullRequests,
+                    );
+
+                    let repository = contribution.pull_request.repository;
+
+                    if let Some(reviews) = contribution.pull_request.reviews {
+                        if reviews.page_info.has_next_page {
+                            pending.push(PendingFetch::new(
+                                SubResource::Reviews,
+                                &contribution.pull_request.id,
+                                &repository.name_with_owner,
+                                contribution.pull_request.number,
+                            ));
+                        } else {
+                            if let Some(review_collection) = reviews.nodes {
+                                for review in review_collection.into_iter().flatten() {
+                                    if let Some(published_at) = review.published_at {
+                                        pull_request_dto.add_review(
+                                            &contribution.pull_request.id,
+                                            Review {
+                                                id: review.id,
+                                                occurred_at: published_at,
+                                                viewer_did_author: review.viewer_did_author,
+                                                state: review.state.into(),
+                                                body: review.body,
+                                                author: review.author.into(),
+                                            },
+                                        );
                                     }
                                 }
                             }
                         }
+                    }
 
-                        if contribution.pull_request.commits.page_info.has_next_page {
-                            fetch_pull_request_commits(
-                                repository.name_with_owner.clone(),
-                                contribution.pull_request.number.clone(),
-                                &contribution.pull_request.id.clone(),
-                                token.clone(),
-                                &mut pull_request_dto,
-                            )
-                            .await?;
-                        } else {
-                            for commit_collection in contribution.pull_request.commits.nodes {
-                                for commit in commit_collection {
-                                    if let Some(commit) = commit {
-                                        if let Some(pushed_date) = commit.commit.pushed_date {
-                                            let mut author_id: String = String::from("");
-
-                                            if let Some(author) = commit.commit.author {
-                                                if let Some(user) = author.user {
-                                                    author_id = user.id;
-                                                }
-                                            }
-
-                                            pull_request_dto.add_commit(
-                                                &contribution.pull_request.id,
-                                                commit.id,
-                                                pushed_date,
-                                                commit.commit.abbreviated_oid,
-                                                author_id,
-                                            );
-                                        }
-                                    }
+                    if contribution.pull_request.commits.page_info.has_next_page {
+                        pending.push(PendingFetch::new(
+                            SubResource::Commits,
+                            &contribution.pull_request.id,
+                            &repository.name_with_owner,
+                            contribution.pull_request.number,
+                        ));
+                    } else {
+                        if let Some(commit_collection) = contribution.pull_request.commits.nodes {
+                            for commit in commit_collection.into_iter().flatten() {
+                                if let Some(pushed_date) = commit.commit.pushed_date {
+                                    pull_request_dto.add_commit(
+                                        &contribution.pull_request.id,
+                                        Commit {
+                                            id: commit.id,
+                                            hash: commit.commit.abbreviated_oid,
+                                            message_headline: commit.commit.message_headline,
+                                            author: commit.commit.author.into(),
+                                            occurred_at: pushed_date,
+                                        },
+                                    );
                                 }
                             }
                         }
@@ -177,10 +144,12 @@ pub async fn get_pull_request_contributions(
                     continue;
                 }
             }
-
-            break;
         }
+
+        break;
     }
 
+    fetch_pending(client, pending, pull_request_dto).await?;
+
     Ok(pull_request_dto)
 }
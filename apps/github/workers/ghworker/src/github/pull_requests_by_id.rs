@@ -0,0 +1,117 @@
+use crate::dto::pull_requests::{Commit, PullRequest, Review};
+use crate::dto::PullRequestsDTO;
+use crate::github::client::GithubClient;
+use crate::github::queries::{pull_requests_by_id_query, PullRequestsByIdQuery};
+use crate::github::sub_resources::{fetch_pending, PendingFetch, SubResource};
+use anyhow::*;
+use log::debug;
+
+const MAX_IDS_PER_QUERY: usize = 100;
+
+pub async fn fetch_pull_requests_by_id(
+    client: &GithubClient,
+    ids: &[String],
+) -> Result<pull_requests_by_id_query::ResponseData> {
+    client
+        .query::<PullRequestsByIdQuery>(pull_requests_by_id_query::Variables { ids: ids.to_vec() })
+        .await
+        .context("Retrieving the stored pull requests by id")
+}
+
+pub async fn get_pull_requests_by_id<'a>(
+    client: &GithubClient,
+    ids: &[String],
+    pull_request_dto: &'a mut PullRequestsDTO,
+) -> Result<&'a PullRequestsDTO> {
+    let mut pending = Vec::new();
+
+    for chunk in ids.chunks(MAX_IDS_PER_QUERY) {
+        debug!("Refreshing {} stored pull requests...", chunk.len());
+        let pull_requests_data = fetch_pull_requests_by_id(client, chunk).await?;
+
+        for node in pull_requests_data.nodes.into_iter().flatten() {
+            let pull_request = match node.on {
+                pull_requests_by_id_query::PullRequestsByIdQueryNodesOn::PullRequest(
+                    pull_request,
+                ) => pull_request,
+                _ => continue,
+            };
+
+            pull_request_dto.add_pull_request(PullRequest {
+                id: pull_request.id.clone(),
+                author: pull_request.author.into(),
+                title: pull_request.title.clone(),
+                date_opened: pull_request.created_at,
+                date_closed: pull_request.closed_at,
+                date_merged: pull_request.merged_at,
+                merged: pull_request.merged,
+                number: pull_request.number,
+                repository: pull_request.repository.name_with_owner.clone(),
+                additions: pull_request.additions,
+                deletions: pull_request.deletions,
+                changed_files: pull_request.changed_files,
+                base_branch: pull_request.base_ref_name.clone(),
+                head_branch: pull_request.head_ref_name.clone(),
+                reviews: Vec::new(),
+                commits: Vec::new(),
+            });
+
+            let repository = pull_request.repository;
+
+            if let Some(reviews) = pull_request.reviews {
+                if reviews.page_info.has_next_page {
+                    pending.push(PendingFetch::new(
+                        SubResource::Reviews,
+                        &pull_request.id,
+                        &repository.name_with_owner,
+                        pull_request.number,
+                    ));
+                } else if let Some(review_collection) = reviews.nodes {
+                    for review in review_collection.into_iter().flatten() {
+                        if let Some(published_at) = review.published_at {
+                            pull_request_dto.add_review(
+                                &pull_request.id,
+                                Review {
+                                    id: review.id,
+                                    occurred_at: published_at,
+                                    viewer_did_author: review.viewer_did_author,
+                                    state: review.state.into(),
+                                    body: review.body,
+                                    author: review.author.into(),
+                                },
+                            );
+                        }
+                    }
+                }
+            }
+
+            if pull_request.commits.page_info.has_next_page {
+                pending.push(PendingFetch::new(
+                    SubResource::Commits,
+                    &pull_request.id,
+                    &repository.name_with_owner,
+                    pull_request.number,
+                ));
+            } else if let Some(commit_collection) = pull_request.commits.nodes {
+                for commit in commit_collection.into_iter().flatten() {
+                    if let Some(pushed_date) = commit.commit.pushed_date {
+                        pull_request_dto.add_commit(
+                            &pull_request.id,
+                            Commit {
+                                id: commit.id,
+                                hash: commit.commit.abbreviated_oid,
+                                message_headline: commit.commit.message_headline,
+                                author: commit.commit.author.into(),
+                                occurred_at: pushed_date,
+                            },
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fetch_pending(client, pending, pull_request_dto).await?;
+
+    Ok(pull_request_dto)
+}
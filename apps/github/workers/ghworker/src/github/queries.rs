@@ -0,0 +1,178 @@
+use crate::dto::{Author, AuthorKind, ReviewState};
+use graphql_client::GraphQLQuery;
+
+type Date = chrono::NaiveDate;
+type DateTime = chrono::DateTime<chrono::Utc>;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct PullRequestContributionsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct PullRequestReviewContributionsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct PullRequestsByIdQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct PullRequestReviewsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct PullRequestCommitsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct ContributionYearsQuery;
+
+#[derive(GraphQLQuery)]
+#[graphql(
+    schema_path = "schemas/github_schema.graphql",
+    query_path = "schemas/queries.graphql",
+    response_derives = "Debug"
+)]
+pub struct ContributionsQuery;
+
+macro_rules! impl_review_state_from {
+    ($($query:ident),*) => {
+        $(
+            impl From<$query::PullRequestReviewState> for ReviewState {
+                fn from(state: $query::PullRequestReviewState) -> ReviewState {
+                    match state {
+                        $query::PullRequestReviewState::APPROVED => ReviewState::Approved,
+                        $query::PullRequestReviewState::CHANGES_REQUESTED => {
+                            ReviewState::ChangesRequested
+                        }
+                        $query::PullRequestReviewState::DISMISSED => ReviewState::Dismissed,
+                        $query::PullRequestReviewState::COMMENTED => ReviewState::Commented,
+                        _ => ReviewState::Pending,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_review_state_from!(
+    pull_request_contributions_query,
+    pull_request_review_contributions_query,
+    pull_requests_by_id_query,
+    pull_request_reviews_query
+);
+
+macro_rules! impl_author_from_actor {
+    ($($query:ident::{$actor:ident, $on:ident}),*) => {
+        $(
+            impl From<Option<$query::$actor>> for Author {
+                fn from(actor: Option<$query::$actor>) -> Author {
+                    let actor = match actor {
+                        Some(actor) => actor,
+                        None => return Author::ghost(),
+                    };
+
+                    match actor.on {
+                        $query::$on::User(user) => Author::new(AuthorKind::User, user.id, actor.login),
+                        $query::$on::Bot(bot) => Author::new(AuthorKind::Bot, bot.id, actor.login),
+                        $query::$on::Mannequin(mannequin) => {
+                            Author::new(AuthorKind::Mannequin, mannequin.id, actor.login)
+                        }
+                        $query::$on::Organization(organization) => {
+                            Author::new(AuthorKind::Organization, organization.id, actor.login)
+                        }
+                        $query::$on::EnterpriseUserAccount(account) => {
+                            Author::new(AuthorKind::EnterpriseUserAccount, account.id, actor.login)
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+impl_author_from_actor!(
+    pull_request_contributions_query::{
+        PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestAuthor,
+        PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestAuthorOn
+    },
+    pull_request_contributions_query::{
+        PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestReviewsNodesAuthor,
+        PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestReviewsNodesAuthorOn
+    },
+    pull_request_review_contributions_query::{
+        PullRequestReviewContributionsQueryUserContributionsCollectionPullRequestReviewContributionsNodesPullRequestAuthor,
+        PullRequestReviewContributionsQueryUserContributionsCollectionPullRequestReviewContributionsNodesPullRequestAuthorOn
+    },
+    pull_request_review_contributions_query::{
+        PullRequestReviewContributionsQueryUserContributionsCollectionPullRequestReviewContributionsNodesPullRequestReviewsNodesAuthor,
+        PullRequestReviewContributionsQueryUserContributionsCollectionPullRequestReviewContributionsNodesPullRequestReviewsNodesAuthorOn
+    },
+    pull_requests_by_id_query::{
+        PullRequestsByIdQueryNodesOnPullRequestAuthor,
+        PullRequestsByIdQueryNodesOnPullRequestAuthorOn
+    },
+    pull_requests_by_id_query::{
+        PullRequestsByIdQueryNodesOnPullRequestReviewsNodesAuthor,
+        PullRequestsByIdQueryNodesOnPullRequestReviewsNodesAuthorOn
+    },
+    pull_request_reviews_query::{
+        PullRequestReviewsQueryRepositoryPullRequestReviewsNodesAuthor,
+        PullRequestReviewsQueryRepositoryPullRequestReviewsNodesAuthorOn
+    }
+);
+
+macro_rules! impl_author_from_git_actor {
+    ($($query:ident::$git_actor:ident),*) => {
+        $(
+            impl From<Option<$query::$git_actor>> for Author {
+                fn from(git_actor: Option<$query::$git_actor>) -> Author {
+                    let git_actor = match git_actor {
+                        Some(git_actor) => git_actor,
+                        None => return Author::unlinked(),
+                    };
+                    let mut author = match git_actor.user {
+                        Some(user) => Author::new(AuthorKind::User, user.id, user.login),
+                        None => Author::unlinked(),
+                    };
+
+                    author.name = git_actor.name;
+                    author.email = git_actor.email;
+                    author
+                }
+            }
+        )*
+    };
+}
+
+impl_author_from_git_actor!(
+    pull_request_contributions_query::PullRequestContributionsQueryUserContributionsCollectionPullRequestContributionsNodesPullRequestCommitsNodesCommitAuthor,
+    pull_request_review_contributions_query::PullRequestReviewContributionsQueryUserContributionsCollectionPullRequestReviewContributionsNodesPullRequestCommitsNodesCommitAuthor,
+    pull_requests_by_id_query::PullRequestsByIdQueryNodesOnPullRequestCommitsNodesCommitAuthor,
+    pull_request_commits_query::PullRequestCommitsQueryRepositoryPullRequestCommitsNodesCommitAuthor
+);
@@ -0,0 +1,140 @@
+use crate::metrics;
+use async_std::task;
+use chrono::{DateTime, TimeZone, Utc};
+use isahc::http::header::HeaderMap;
+use log::{debug, info};
+use serde_json::Value;
+use std::sync::Mutex;
+use std::time::Duration;
+
+const RETRY_AFTER: &str = "retry-after";
+const RATE_LIMIT_REMAINING: &str = "x-ratelimit-remaining";
+const RATE_LIMIT_RESET: &str = "x-ratelimit-reset";
+const FALLBACK_PAUSE: Duration = Duration::from_secs(60);
+pub const MIN_PAUSE: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    pub cost: i64,
+    pub remaining: i64,
+    pub reset_at: DateTime<Utc>,
+}
+
+#[derive(Default)]
+pub struct RateLimiter {
+    budget: Mutex<Option<RateLimit>>,
+}
+
+impl RateLimiter {
+    pub fn new() -> RateLimiter {
+        RateLimiter::default()
+    }
+
+    pub fn current(&self) -> Option<RateLimit> {
+        *self.budget.lock().unwrap()
+    }
+
+    pub fn record_headers(&self, headers: &HeaderMap) {
+        let remaining = header_as_i64(headers, RATE_LIMIT_REMAINING);
+        let reset_at = header_as_i64(headers, RATE_LIMIT_RESET)
+            .and_then(|epoch| Utc.timestamp_opt(epoch, 0).single());
+
+        if let (Some(remaining), Some(reset_at)) = (remaining, reset_at) {
+            let mut budget = self.budget.lock().unwrap();
+            let cost = budget.map(|budget| budget.cost).unwrap_or(1);
+            let rate_limit = RateLimit {
+                cost,
+                remaining,
+                reset_at,
+            };
+            metrics::record_rate_limit(&rate_limit);
+            *budget = Some(rate_limit);
+        }
+    }
+
+    pub fn record_response(&self, response: &Value) {
+        let rate_limit = &response["data"]["rateLimit"];
+        let reset_at = rate_limit["resetAt"]
+            .as_str()
+            .and_then(|reset_at| DateTime::parse_from_rfc3339(reset_at).ok());
+
+        if let (Some(cost), Some(remaining), Some(reset_at)) = (
+            rate_limit["cost"].as_i64(),
+            rate_limit["remaining"].as_i64(),
+            reset_at,
+        ) {
+            let rate_limit = RateLimit {
+                cost,
+                remaining,
+                reset_at: reset_at.with_timezone(&Utc),
+            };
+            debug!(
+                "Github rate limit: query cost {}, {} points remaining, resets at {}",
+                rate_limit.cost, rate_limit.remaining, rate_limit.reset_at
+            );
+            metrics::record_rate_limit(&rate_limit);
+            *self.budget.lock().unwrap() = Some(rate_limit);
+        }
+    }
+
+    pub fn mark_exhausted(&self) {
+        let mut budget = self.budget.lock().unwrap();
+        let rate_limit = match *budget {
+            Some(rate_limit) => RateLimit {
+                remaining: 0,
+                ..rate_limit
+            },
+            None => RateLimit {
+                cost: 1,
+                remaining: 0,
+                reset_at: Utc::now() + chrono::Duration::from_std(FALLBACK_PAUSE).unwrap(),
+            },
+        };
+        metrics::record_rate_limit(&rate_limit);
+        *budget = Some(rate_limit);
+    }
+
+    pub async fn wait_for_budget(&self) {
+        loop {
+            let rate_limit = {
+                let mut budget = self.budget.lock().unwrap();
+                match budget.as_mut() {
+                    Some(rate_limit) if rate_limit.remaining < rate_limit.cost.max(1) => {
+                        *rate_limit
+                    }
+                    Some(rate_limit) => {
+                        rate_limit.remaining -= rate_limit.cost.max(1);
+                        return;
+                    }
+                    None => return,
+                }
+            };
+
+            if let Ok(pause) = (rate_limit.reset_at - Utc::now()).to_std() {
+                info!(
+                    "Github rate limit exhausted ({} points remaining), pausing until {}",
+                    rate_limit.remaining, rate_limit.reset_at
+                );
+                task::sleep(pause).await;
+            }
+
+            let mut budget = self.budget.lock().unwrap();
+            if let Some(current) = *budget {
+                if current.reset_at <= Utc::now() {
+                    *budget = None;
+                }
+            }
+        }
+    }
+}
+
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    header_as_i64(headers, RETRY_AFTER).map(|seconds| Duration::from_secs(seconds.max(0) as u64))
+}
+
+fn header_as_i64(headers: &HeaderMap, name: &str) -> Option<i64> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse().ok())
+}
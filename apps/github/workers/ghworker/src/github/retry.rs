@@ -0,0 +1,52 @@
+use crate::github::error::GithubError;
+use rand::Rng;
+use std::time::Duration;
+use structopt::StructOpt;
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct RetryPolicy {
+    #[structopt(
+        long = "github-max-attempts",
+        env = "GITHUB_MAX_ATTEMPTS",
+        default_value = "5"
+    )]
+    pub max_attempts: u32,
+    #[structopt(
+        long = "github-retry-base-delay-ms",
+        env = "GITHUB_RETRY_BASE_DELAY_MS",
+        default_value = "500"
+    )]
+    pub base_delay_ms: u64,
+    #[structopt(
+        long = "github-retry-max-delay-ms",
+        env = "GITHUB_RETRY_MAX_DELAY_MS",
+        default_value = "30000"
+    )]
+    pub max_delay_ms: u64,
+    #[structopt(
+        long = "github-retry-jitter",
+        env = "GITHUB_RETRY_JITTER",
+        default_value = "0.5"
+    )]
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub fn should_retry(&self, attempt: u32, err: &anyhow::Error) -> bool {
+        attempt < self.max_attempts
+            && err
+                .downcast_ref::<GithubError>()
+                .is_some_and(GithubError::is_retryable)
+    }
+
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(31);
+        let delay = self
+            .base_delay_ms
+            .saturating_mul(1 << exponent)
+            .min(self.max_delay_ms);
+        let jitter = self.jitter.clamp(0.0, 1.0) * rand::thread_rng().gen::<f64>();
+
+        Duration::from_millis((delay as f64 * (1.0 - jitter)) as u64)
+    }
+}
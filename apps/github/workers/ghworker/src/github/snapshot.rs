@@ -0,0 +1,156 @@
+use anyhow::*;
+use isahc::http::StatusCode;
+use log::debug;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct Interaction {
+    pub operation: String,
+    #[serde(default)]
+    pub variables: Value,
+    #[serde(default = "ok_status")]
+    pub status: u16,
+    pub response: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default)]
+pub struct Snapshot {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Snapshot {
+    pub fn load(path: &Path) -> Result<Snapshot> {
+        let contents =
+            fs::read(path).context(format!("Reading the snapshot {}", path.display()))?;
+
+        serde_json::from_slice(&contents)
+            .context(format!("Parsing the snapshot {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let contents = serde_json::to_vec_pretty(self)?;
+        fs::write(path, contents).context(format!("Writing the snapshot {}", path.display()))
+    }
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    snapshot: Mutex<Snapshot>,
+}
+
+impl Recorder {
+    pub fn new(path: &Path) -> Result<Recorder> {
+        let snapshot = if path.exists() {
+            Snapshot::load(path)?
+        } else {
+            Snapshot::default()
+        };
+
+        Ok(Recorder {
+            path: path.to_path_buf(),
+            snapshot: Mutex::new(snapshot),
+        })
+    }
+
+    pub fn record(
+        &self,
+        operation: &str,
+        variables: Value,
+        status: StatusCode,
+        body: &str,
+    ) -> Result<()> {
+        let response = serde_json::from_str(body).unwrap_or_else(|_| Value::String(body.into()));
+        let mut snapshot = self.snapshot.lock().unwrap();
+
+        snapshot.interactions.push(Interaction {
+            operation: operation.to_string(),
+            variables,
+            status: status.as_u16(),
+            response,
+        });
+        debug!(
+            "Recorded the {} response to {}",
+            operation,
+            self.path.display()
+        );
+
+        snapshot.save(&self.path)
+    }
+}
+
+pub struct Replayer {
+    path: PathBuf,
+    interactions: Vec<Interaction>,
+    served: Mutex<Vec<bool>>,
+}
+
+impl Replayer {
+    pub fn load(path: &Path) -> Result<Replayer> {
+        let interactions = Snapshot::load(path)?.interactions;
+
+        Ok(Replayer {
+            path: path.to_path_buf(),
+            served: Mutex::new(vec![false; interactions.len()]),
+            interactions,
+        })
+    }
+
+    pub fn replay(&self, operation: &str, variables: &Value) -> Result<(StatusCode, String)> {
+        let mut served = self.served.lock().unwrap();
+        let index = self
+            .interactions
+            .iter()
+            .enumerate()
+            .find(|(index, interaction)| {
+                !served[*index]
+                    && interaction.operation == operation
+                    && matches(&interaction.variables, variables)
+            })
+            .map(|(index, _)| index)
+            .ok_or_else(|| {
+                anyhow!(
+                    "{} has no unused {} response for the variables {}",
+                    self.path.display(),
+                    operation,
+                    variables
+                )
+            })?;
+        served[index] = true;
+
+        let interaction = &self.interactions[index];
+        let body = match &interaction.response {
+            Value::String(body) => body.clone(),
+            response => response.to_string(),
+        };
+        debug!(
+            "Replaying the {} response {} from {}",
+            operation,
+            index,
+            self.path.display()
+        );
+
+        Ok((StatusCode::from_u16(interaction.status)?, body))
+    }
+}
+
+fn matches(recorded: &Value, variables: &Value) -> bool {
+    match recorded {
+        Value::Null => true,
+        Value::Object(recorded) => recorded
+            .iter()
+            .all(|(name, value)| variables.get(name).unwrap_or(&Value::Null) == value),
+        recorded => recorded == variables,
+    }
+}
+
+fn ok_status() -> u16 {
+    200
+}
@@ -0,0 +1,104 @@
+use crate::dto::pull_requests::{Commit, Review};
+use crate::dto::PullRequestsDTO;
+use crate::github::client::GithubClient;
+use crate::github::commits::fetch_pull_request_commits;
+use crate::github::reviews::fetch_pull_request_reviews;
+use anyhow::*;
+use futures::stream::{self, StreamExt, TryStreamExt};
+use log::debug;
+
+#[derive(Debug, Clone, Copy)]
+pub enum SubResource {
+    Reviews,
+    Commits,
+}
+
+#[derive(Debug, Clone)]
+pub struct PendingFetch {
+    pub sub_resource: SubResource,
+    pub pull_request_id: String,
+    pub name_with_owner: String,
+    pub number: i64,
+}
+
+impl PendingFetch {
+    pub fn new(
+        sub_resource: SubResource,
+        pull_request_id: &str,
+        name_with_owner: &str,
+        number: i64,
+    ) -> PendingFetch {
+        PendingFetch {
+            sub_resource,
+            pull_request_id: pull_request_id.to_string(),
+            name_with_owner: name_with_owner.to_string(),
+            number,
+        }
+    }
+}
+
+enum Fetched {
+    Reviews(String, Vec<Review>),
+    Commits(String, Vec<Commit>),
+}
+
+async fn fetch(client: &GithubClient, pending: PendingFetch) -> Result<Fetched> {
+    let PendingFetch {
+        sub_resource,
+        pull_request_id,
+        name_with_owner,
+        number,
+    } = pending;
+
+    match sub_resource {
+        SubResource::Reviews => {
+            fetch_pull_request_reviews(client, name_with_owner, number, &pull_request_id)
+                .await
+                .map(|reviews| Fetched::Reviews(pull_request_id, reviews))
+        }
+        SubResource::Commits => {
+            fetch_pull_request_commits(client, name_with_owner, number, &pull_request_id)
+                .await
+                .map(|commits| Fetched::Commits(pull_request_id, commits))
+        }
+    }
+}
+
+pub async fn fetch_pending(
+    client: &GithubClient,
+    pending: Vec<PendingFetch>,
+    pull_request_dto: &mut PullRequestsDTO,
+) -> Result<()> {
+    if pending.is_empty() {
+        return Ok(());
+    }
+
+    debug!(
+        "Fetching the remaining pages of {} pull request sub-resources, {} at a time",
+        pending.len(),
+        client.concurrency()
+    );
+
+    let fetched: Vec<Fetched> = stream::iter(pending)
+        .map(|pending| fetch(client, pending))
+        .buffer_unordered(client.concurrency())
+        .try_collect()
+        .await?;
+
+    for fetched in fetched {
+        match fetched {
+            Fetched::Reviews(pull_request_id, reviews) => {
+                for review in reviews {
+                    pull_request_dto.add_review(&pull_request_id, review);
+                }
+            }
+            Fetched::Commits(pull_request_id, commits) => {
+                for commit in commits {
+                    pull_request_dto.add_commit(&pull_request_id, commit);
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
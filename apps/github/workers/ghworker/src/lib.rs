@@ -1,42 +1,262 @@
 use anyhow::*;
+use chrono::Utc;
+use database::repository::GithubAccountRepository;
+use dto::{PullRequestsDTO, SyncWindow};
+use log::warn;
 use sqlx::PgPool;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use store::{Store, StoreSummary};
 use structopt::StructOpt;
 use worker::Worker;
 
 mod database;
-mod dto;
+mod diff;
+pub mod dto;
+mod export;
 mod github;
+mod metrics;
+mod queue;
 mod store;
 mod worker;
 
+pub use database::migrations::migrate;
+pub use database::repository::UpsertOutcome;
+pub use diff::{ColumnChange, RowDiff, SyncDiff};
+pub use export::{ExportFormat, ExportSource};
+pub use github::{
+    get_contribution_calendar, get_pull_request_contributions,
+    get_pull_request_review_contributions, get_pull_requests_by_id, GithubApi, GithubClient,
+    GithubError, GithubOptions,
+};
+pub use metrics::serve_metrics;
+pub use queue::{consume, replay_dead_letters, QueueOptions};
+
 #[derive(StructOpt, Debug)]
 #[structopt(
     name = "ghworker",
     about = "A worker to pull data from github for our users"
 )]
 pub struct GithubWorker {
-    #[structopt(short, long, env = "GITHUB_API_TOKEN")]
+    #[structopt(long, env = "DATABASE_URL")]
+    pub database: Option<String>,
+    #[structopt(subcommand)]
+    pub command: Command,
+}
+
+#[derive(StructOpt, Debug)]
+pub enum Command {
+    #[structopt(about = "Applies the pending database migrations")]
+    Migrate,
+    #[structopt(about = "Syncs the users requested on the queue")]
+    Consume(ConsumeCommand),
+    #[structopt(about = "Syncs a single user and prints a summary")]
+    Sync(SyncCommand),
+    #[structopt(about = "Writes a user's contributions to CSV or JSON files")]
+    Export(ExportCommand),
+    #[structopt(about = "Republishes the dead letters onto the queue topic")]
+    ReplayDlq(QueueOptions),
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ConsumeCommand {
+    #[structopt(long, env = "METRICS_ADDRESS")]
+    pub metrics_address: Option<String>,
+    #[structopt(flatten)]
+    pub queue: QueueOptions,
+    #[structopt(flatten)]
+    pub sync: SyncOptions,
+    #[structopt(flatten)]
+    pub github: GithubOptions,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct SyncCommand {
+    #[structopt(short, long)]
+    pub username: String,
+    #[structopt(long)]
+    pub user_id: Option<String>,
+    #[structopt(short, long, env = "GITHUB_API_TOKEN", hide_env_values = true)]
     pub token: String,
+    #[structopt(long, conflicts_with = "backfill")]
+    pub dry_run: bool,
+    #[structopt(flatten)]
+    pub sync: SyncOptions,
+    #[structopt(flatten)]
+    pub github: GithubOptions,
+}
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct SyncOptions {
+    #[structopt(long)]
+    pub full_resync: bool,
+    #[structopt(long)]
+    pub backfill: bool,
+    #[structopt(long, env = "DATABASE_BATCH_SIZE", default_value = "500")]
+    pub batch_size: usize,
+}
+
+#[derive(StructOpt, Debug)]
+pub struct ExportCommand {
     #[structopt(short, long)]
     pub username: String,
-    #[structopt(long, env = "DATABASE_URL")]
-    pub database: String,
-    #[structopt(long, env = "QUEUE_URL")]
-    pub queue_url: String,
-    #[structopt(long, env = "QUEUE_TOPIC")]
-    pub queue_topic: String,
-    #[structopt(long, env = "QUEUE_GROUP")]
-    pub queue_group: String,
+    #[structopt(long)]
+    pub user_id: Option<String>,
+    #[structopt(long, default_value = "json", possible_values = &["csv", "json", "ndjson"])]
+    pub format: ExportFormat,
+    #[structopt(long, parse(from_os_str))]
+    pub out: PathBuf,
+    #[structopt(long, default_value = "database", possible_values = &["database", "github"])]
+    pub source: ExportSource,
+    #[structopt(
+        short,
+        long,
+        env = "GITHUB_API_TOKEN",
+        hide_env_values = true,
+        required_if("source", "github")
+    )]
+    pub token: Option<String>,
+    #[structopt(flatten)]
+    pub github: GithubOptions,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct SyncReport {
+    pub summary: StoreSummary,
+    pub window: Option<SyncWindow>,
+    pub duration: Duration,
 }
 
-pub async fn run(db_pool: &PgPool, username: String, token: String) -> Result<()> {
-    let mut worker = Worker::new(db_pool);
+pub async fn run(
+    db_pool: &PgPool,
+    github: &GithubApi,
+    sync_options: &SyncOptions,
+    user_id: String,
+    username: String,
+    token: String,
+) -> Result<SyncReport> {
+    let started = Instant::now();
+    let client = github.client(token);
+    let mut worker = Worker::new(db_pool, user_id, sync_options.batch_size);
+
+    if sync_options.backfill {
+        worker
+            .backfill(&client, username.clone(), sync_options.full_resync)
+            .await?;
+    } else {
+        worker
+            .fetch_data_from_github(&client, username.clone(), sync_options.full_resync)
+            .await?
+            .store_data()
+            .await?;
+    }
+
+    if let Err(e) = worker
+        .sync_contribution_calendar(&client, username.clone())
+        .await
+    {
+        warn!(
+            "Stored the contributions of {} but not their contribution calendar: {:#}",
+            username, e
+        );
+    }
+
+    Ok(SyncReport {
+        summary: worker.summary(),
+        window: worker.window(),
+        duration: started.elapsed(),
+    })
+}
+
+#[derive(Debug)]
+pub struct DryRunReport {
+    pub diff: SyncDiff,
+    pub window: Option<SyncWindow>,
+    pub duration: Duration,
+}
+
+pub async fn dry_run(
+    db_pool: &PgPool,
+    github: &GithubApi,
+    sync_options: &SyncOptions,
+    user_id: String,
+    username: String,
+    token: String,
+) -> Result<DryRunReport> {
+    let started = Instant::now();
+    let client = github.client(token);
+    let mut worker = Worker::new(db_pool, user_id, sync_options.batch_size);
 
     worker
-        .fetch_data_from_github(username, token)
-        .await?
-        .store_data()
+        .fetch_data_from_github(&client, username, sync_options.full_resync)
         .await?;
 
-    Ok(())
+    let diff = match worker.pull_requests() {
+        Some(pull_requests) => diff::diff_pull_requests_dto(db_pool, pull_requests).await?,
+        None => SyncDiff::default(),
+    };
+
+    Ok(DryRunReport {
+        diff,
+        window: worker.window(),
+        duration: started.elapsed(),
+    })
+}
+
+pub async fn export_from_database(
+    db_pool: &PgPool,
+    export: &ExportCommand,
+) -> Result<Vec<PathBuf>> {
+    let user_id = match &export.user_id {
+        Some(user_id) => user_id.clone(),
+        None => resolve_user_id(db_pool, &export.username).await?,
+    };
+    let pull_requests = Store::load_pull_requests_dto(db_pool, &user_id, &export.username).await?;
+
+    export::export_pull_requests_dto(&pull_requests, &export.username, export.format, &export.out)
+}
+
+pub async fn export_from_github(export: &ExportCommand) -> Result<Vec<PathBuf>> {
+    let token = export
+        .token
+        .clone()
+        .context("A github token is needed to export from github")?;
+    let client = GithubClient::new(&export.github, token)?;
+    let window = SyncWindow {
+        from: None,
+        to: Utc::now(),
+    };
+    let mut pull_requests = PullRequestsDTO::new();
+    let mut review_pull_requests = PullRequestsDTO::new();
+
+    futures::try_join!(
+        get_pull_request_contributions(
+            &client,
+            export.username.clone(),
+            &window,
+            &mut pull_requests,
+        ),
+        get_pull_request_review_contributions(
+            &client,
+            export.username.clone(),
+            &window,
+            &mut review_pull_requests,
+        ),
+    )?;
+    pull_requests.merge(review_pull_requests);
+
+    export::export_pull_requests_dto(&pull_requests, &export.username, export.format, &export.out)
+}
+
+pub async fn resolve_user_id(db_pool: &PgPool, username: &str) -> Result<String> {
+    GithubAccountRepository::find_user_id(db_pool, username)
+        .await?
+        .context(format!(
+            "The github account {} is not linked to a user yet, pass --user-id to link it",
+            username
+        ))
+}
+
+pub async fn unregister(db_pool: &PgPool, user_id: &str, username: &str) -> Result<()> {
+    Store::delete_user_data(db_pool, user_id, username).await
 }
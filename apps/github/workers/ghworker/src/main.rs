@@ -2,61 +2,211 @@ use anyhow::*;
 use async_std::task;
 use dotenv::dotenv;
 use env_logger::{Builder, Target};
-use ghworker::GithubWorker;
-use kafka::consumer::{Consumer, FetchOffset};
-use log::{debug, error};
-use serde::{Deserialize, Serialize};
+use ghworker::{
+    Command, DryRunReport, ExportCommand, ExportSource, GithubApi, GithubWorker, SyncCommand,
+    SyncReport, UpsertOutcome,
+};
+use log::{debug, error, info};
 use sqlx::PgPool;
+use std::path::PathBuf;
 use std::process;
 use structopt::StructOpt;
 
-#[derive(Serialize, Deserialize, Debug)]
-struct Event {
-    user_id: String,
-    token: String,
-    username: String,
-}
-
 fn main() -> Result<()> {
     let app = make_app()?;
-    let db_pool: PgPool = task::block_on(connect_to_database(&app.database))?;
+    let database = app.database.as_deref();
 
-    if let Err(e) = task::block_on(connect_to_queue(&app, &db_pool)) {
-        error!("Error: {}", e);
-        process::exit(1);
+    match app.command {
+        Command::Migrate => {
+            let db_pool = connect_or_exit(database);
+            let version = task::block_on(ghworker::migrate(&db_pool)).unwrap_or_else(|e| {
+                error!("Error: {:#}", e);
+                process::exit(1);
+            });
+            info!("The database is at schema version {}", version);
+        }
+        Command::Export(export) => {
+            let files =
+                task::block_on(export_contributions(database, &export)).unwrap_or_else(|e| {
+                    error!("Error: {:#}", e);
+                    process::exit(1);
+                });
+            for file in files {
+                println!("{}", file.display());
+            }
+        }
+        Command::ReplayDlq(queue) => {
+            let replayed = ghworker::replay_dead_letters(&queue).unwrap_or_else(|e| {
+                error!("Error: {:#}", e);
+                process::exit(1);
+            });
+            info!(
+                "Replayed {} dead letters onto {}",
+                replayed, queue.queue_topic
+            );
+        }
+        Command::Sync(sync) if sync.dry_run => {
+            let db_pool = connect_or_exit(database);
+            let report = task::block_on(dry_run_user(&db_pool, &sync)).unwrap_or_else(|e| {
+                error!("Error: {:#}", e);
+                process::exit(1);
+            });
+            print_dry_run(&sync.username, &report);
+        }
+        Command::Sync(sync) => {
+            let db_pool = connect_or_exit(database);
+            let report = task::block_on(sync_user(&db_pool, &sync)).unwrap_or_else(|e| {
+                error!("Error: {:#}", e);
+                process::exit(1);
+            });
+            println!(
+                "Synced {} in {:.1}s",
+                sync.username,
+                report.duration.as_secs_f64()
+            );
+            print_report(&report);
+        }
+        Command::Consume(consume) => {
+            let db_pool = connect_or_exit(database);
+
+            if let Some(address) = &consume.metrics_address {
+                ghworker::serve_metrics(address).unwrap_or_else(|e| {
+                    error!("Error: {:#}", e);
+                    process::exit(1);
+                });
+            }
+
+            if let Err(e) = task::block_on(ghworker::consume(
+                &db_pool,
+                &consume.queue,
+                &consume.github,
+                &consume.sync,
+            )) {
+                error!("Error: {}", e);
+                process::exit(1);
+            }
+        }
     }
 
     Ok(())
 }
 
-async fn connect_to_queue(app: &GithubWorker, db_pool: &PgPool) -> Result<()> {
-    debug!("Connecting to a kafka broker....");
-    debug!("[Queue URL]: {}", app.queue_url);
-    debug!("[Queue Topic]: {}", app.queue_topic);
-    debug!("[Queue Consumer Group]: {}", app.queue_group);
-
-    let mut consumer = Consumer::from_hosts(vec![app.queue_url.clone()])
-        .with_topic(app.queue_topic.clone())
-        .with_fallback_offset(FetchOffset::Earliest)
-        .with_group(app.queue_group.clone())
-        .create()
-        .unwrap();
-
-    loop {
-        for ms in consumer.poll().unwrap().iter() {
-            for m in ms.messages() {
-                let event: Event = serde_json::from_slice(&m.value[..]).unwrap();
-                let key: String = std::str::from_utf8(m.key).unwrap().to_string();
-
-                if key == String::from("REGISTER_PLUGIN:GITHUB") {
-                    debug!("Received an event for the user => {}", &event.username);
-                    ghworker::run(&db_pool, event.username, event.token).await?;
+async fn export_contributions(
+    database: Option<&str>,
+    export: &ExportCommand,
+) -> Result<Vec<PathBuf>> {
+    match export.source {
+        ExportSource::Database => {
+            let db_pool = connect_to_database(database).await?;
+            ghworker::export_from_database(&db_pool, export).await
+        }
+        ExportSource::Github => ghworker::export_from_github(export).await,
+    }
+}
+
+async fn user_id(db_pool: &PgPool, sync: &SyncCommand) -> Result<String> {
+    match &sync.user_id {
+        Some(user_id) => Ok(user_id.clone()),
+        None => ghworker::resolve_user_id(db_pool, &sync.username).await,
+    }
+}
+
+async fn sync_user(db_pool: &PgPool, sync: &SyncCommand) -> Result<SyncReport> {
+    ghworker::run(
+        db_pool,
+        &GithubApi::new(&sync.github)?,
+        &sync.sync,
+        user_id(db_pool, sync).await?,
+        sync.username.clone(),
+        sync.token.clone(),
+    )
+    .await
+}
+
+async fn dry_run_user(db_pool: &PgPool, sync: &SyncCommand) -> Result<DryRunReport> {
+    ghworker::dry_run(
+        db_pool,
+        &GithubApi::new(&sync.github)?,
+        &sync.sync,
+        user_id(db_pool, sync).await?,
+        sync.username.clone(),
+        sync.token.clone(),
+    )
+    .await
+}
+
+fn print_dry_run(username: &str, report: &DryRunReport) {
+    println!(
+        "Dry run for {} in {:.1}s, nothing was written",
+        username,
+        report.duration.as_secs_f64()
+    );
+
+    for rows in &[
+        &report.diff.pull_requests,
+        &report.diff.reviews,
+        &report.diff.commits,
+    ] {
+        for row in rows.iter() {
+            match row.outcome {
+                UpsertOutcome::Inserted => println!("+ {}", row.label),
+                UpsertOutcome::Updated => {
+                    println!("~ {}", row.label);
+                    for change in &row.changes {
+                        println!(
+                            "    {}: {} -> {}",
+                            change.column,
+                            display(&change.from),
+                            display(&change.to)
+                        );
+                    }
                 }
+                UpsertOutcome::Unchanged => {}
             }
-            consumer.consume_messageset(ms).unwrap();
         }
+    }
 
-        consumer.commit_consumed().unwrap();
+    print_report(&SyncReport {
+        summary: report.diff.summary(),
+        window: report.window,
+        duration: report.duration,
+    });
+}
+
+fn display(value: &Option<String>) -> String {
+    match value {
+        Some(value) => format!("{:?}", value),
+        None => String::from("null"),
+    }
+}
+
+fn print_report(report: &SyncReport) {
+    if let Some(window) = report.window {
+        match window.from {
+            Some(from) => println!("  window:            {} to {}", from, window.to),
+            None => println!("  window:            everything up to {}", window.to),
+        }
+    }
+
+    let mut rows = vec![
+        ("pull requests", report.summary.pull_requests),
+        ("reviews", report.summary.reviews),
+        ("commits", report.summary.commits),
+    ];
+    let days = report.summary.contribution_days;
+
+    if days.inserted + days.updated + days.unchanged > 0 {
+        rows.push(("contribution days", days));
+    }
+
+    for (name, counts) in rows {
+        println!(
+            "  {:<18} {} inserted, {} updated, {} unchanged",
+            format!("{}:", name),
+            counts.inserted,
+            counts.updated,
+            counts.unchanged
+        );
     }
 }
 
@@ -74,6 +224,16 @@ fn make_app() -> Result<GithubWorker> {
     Ok(app)
 }
 
-async fn connect_to_database(database_url: &str) -> Result<PgPool> {
+fn connect_or_exit(database_url: Option<&str>) -> PgPool {
+    task::block_on(connect_to_database(database_url)).unwrap_or_else(|e| {
+        error!("Error: {:#}", e);
+        process::exit(1);
+    })
+}
+
+async fn connect_to_database(database_url: Option<&str>) -> Result<PgPool> {
+    let database_url =
+        database_url.context("Pass --database or set DATABASE_URL to use this command")?;
+
     Ok(PgPool::new(database_url).await?)
 }
@@ -0,0 +1,57 @@
+use crate::github::rate_limit::RateLimit;
+use anyhow::*;
+use lazy_static::lazy_static;
+use log::{info, warn};
+use prometheus::{register_int_gauge, Encoder, IntGauge, TextEncoder};
+use std::thread;
+use tiny_http::{Header, Response, Server};
+
+lazy_static! {
+    static ref RATE_LIMIT_REMAINING: IntGauge = register_int_gauge!(
+        "ghworker_github_rate_limit_remaining",
+        "Github rate limit points left in the current window"
+    )
+    .unwrap();
+    static ref RATE_LIMIT_COST: IntGauge = register_int_gauge!(
+        "ghworker_github_rate_limit_cost",
+        "Github rate limit points charged for the last query"
+    )
+    .unwrap();
+    static ref RATE_LIMIT_RESET: IntGauge = register_int_gauge!(
+        "ghworker_github_rate_limit_reset_timestamp_seconds",
+        "When the github rate limit window resets, as a unix timestamp"
+    )
+    .unwrap();
+}
+
+pub fn record_rate_limit(rate_limit: &RateLimit) {
+    RATE_LIMIT_REMAINING.set(rate_limit.remaining);
+    RATE_LIMIT_COST.set(rate_limit.cost);
+    RATE_LIMIT_RESET.set(rate_limit.reset_at.timestamp());
+}
+
+pub fn serve_metrics(address: &str) -> Result<()> {
+    lazy_static::initialize(&RATE_LIMIT_REMAINING);
+    lazy_static::initialize(&RATE_LIMIT_COST);
+    lazy_static::initialize(&RATE_LIMIT_RESET);
+
+    let server = Server::http(address)
+        .map_err(|e| anyhow!("Listening for metrics scrapes on {}: {}", address, e))?;
+    info!("Serving metrics on http://{}/metrics", address);
+
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let mut body = Vec::new();
+
+            if let Err(e) = TextEncoder::new().encode(&prometheus::gather(), &mut body) {
+                warn!("Could not encode the metrics: {}", e);
+            }
+
+            let content_type =
+                Header::from_bytes(&b"Content-Type"[..], TextEncoder::new().format_type()).unwrap();
+            let _ = request.respond(Response::from_data(body).with_header(content_type));
+        }
+    });
+
+    Ok(())
+}
@@ -0,0 +1,143 @@
+use crate::github::GithubError;
+use crate::queue::Delivery;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use kafka::consumer::{Consumer, FetchOffset};
+use kafka::producer::{Producer, Record};
+use log::{debug, info, warn};
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DeadLetter {
+    pub key: String,
+    pub payload: String,
+    pub error_kind: String,
+    pub error: String,
+    pub attempts: u32,
+    pub failed_at: DateTime<Utc>,
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+}
+
+impl DeadLetter {
+    pub fn new(delivery: &Delivery, err: &anyhow::Error, attempts: u32) -> DeadLetter {
+        DeadLetter {
+            key: String::from_utf8_lossy(&delivery.key).into_owned(),
+            payload: String::from_utf8_lossy(&delivery.value).into_owned(),
+            error_kind: error_kind(err).to_string(),
+            error: format!("{:#}", err),
+            attempts,
+            failed_at: Utc::now(),
+            topic: delivery.topic.clone(),
+            partition: delivery.partition,
+            offset: delivery.offset,
+        }
+    }
+
+    pub fn publish(&self, producer: &mut Producer, dead_letter_topic: &str) -> Result<()> {
+        let value = serde_json::to_vec(self)?;
+
+        producer
+            .send(&Record::from_key_value(
+                dead_letter_topic,
+                self.key.as_str(),
+                value,
+            ))
+            .map_err(|e| anyhow!("Publishing to the dead letter topic: {}", e))?;
+
+        info!(
+            "Published the {} event at {}:{}@{} to {} after {} attempts",
+            self.key, self.topic, self.partition, self.offset, dead_letter_topic, self.attempts
+        );
+
+        Ok(())
+    }
+}
+
+pub fn error_kind(err: &anyhow::Error) -> &'static str {
+    for cause in err.chain() {
+        if let Some(github_error) = cause.downcast_ref::<GithubError>() {
+            return match github_error {
+                GithubError::Http { status: 401, .. } => "github_unauthorized",
+                GithubError::Http { status: 403, .. }
+                | GithubError::Http { status: 429, .. }
+                | GithubError::RateLimited { .. } => "github_rate_limited",
+                GithubError::Http { status: 404, .. } => "github_not_found",
+                GithubError::GraphQL(errors)
+                    if errors
+                        .iter()
+                        .any(|error| error.error_type.as_deref() == Some("NOT_FOUND")) =>
+                {
+                    "github_not_found"
+                }
+                GithubError::Transport(_) => "github_unreachable",
+                _ => "github",
+            };
+        }
+
+        if cause.downcast_ref::<sqlx::Error>().is_some() {
+            return "database";
+        }
+    }
+
+    "unknown"
+}
+
+pub fn replay(queue_url: &str, dead_letter_topic: &str, group: &str, topic: &str) -> Result<usize> {
+    let mut consumer = Consumer::from_hosts(vec![queue_url.to_string()])
+        .with_topic(dead_letter_topic.to_string())
+        .with_fallback_offset(FetchOffset::Earliest)
+        .with_group(group.to_string())
+        .create()
+        .map_err(|e| anyhow!("Creating the dead letter consumer: {}", e))?;
+    let mut producer = Producer::from_hosts(vec![queue_url.to_string()])
+        .create()
+        .map_err(|e| anyhow!("Creating the kafka producer: {}", e))?;
+    let mut replayed = 0;
+
+    loop {
+        let message_sets = consumer
+            .poll()
+            .map_err(|e| anyhow!("Polling the dead letter topic: {}", e))?;
+
+        if message_sets.is_empty() {
+            break;
+        }
+
+        for ms in message_sets.iter() {
+            for m in ms.messages() {
+                let dead_letter: DeadLetter = match serde_json::from_slice(m.value) {
+                    Ok(dead_letter) => dead_letter,
+                    Err(e) => {
+                        warn!("Skipping the dead letter at offset {}: {}", m.offset, e);
+                        continue;
+                    }
+                };
+
+                producer
+                    .send(&Record::from_key_value(
+                        topic,
+                        dead_letter.key.as_str(),
+                        dead_letter.payload.as_str(),
+                    ))
+                    .map_err(|e| anyhow!("Republishing a dead letter: {}", e))?;
+                debug!(
+                    "Replayed the {} event that failed with {}: {}",
+                    dead_letter.key, dead_letter.error_kind, dead_letter.error
+                );
+                replayed += 1;
+            }
+
+            consumer
+                .consume_messageset(ms)
+                .map_err(|e| anyhow!("Marking the dead letters as consumed: {}", e))?;
+        }
+
+        consumer
+            .commit_consumed()
+            .map_err(|e| anyhow!("Committing the dead letter offsets: {}", e))?;
+    }
+
+    Ok(replayed)
+}
@@ -0,0 +1,79 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+
+pub const REGISTER_PLUGIN: &str = "REGISTER_PLUGIN:GITHUB";
+pub const SYNC: &str = "SYNC:GITHUB";
+pub const RESYNC: &str = "RESYNC:GITHUB";
+pub const UNREGISTER_PLUGIN: &str = "UNREGISTER_PLUGIN:GITHUB";
+pub const TOKEN_UPDATED: &str = "TOKEN_UPDATED:GITHUB";
+
+#[derive(Deserialize, Debug)]
+pub struct SyncRequest {
+    pub user_id: String,
+    pub token: String,
+    pub username: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct UnregisterRequest {
+    pub user_id: String,
+    pub username: String,
+}
+
+#[derive(Debug)]
+pub enum Event {
+    RegisterPlugin(SyncRequest),
+    Sync(SyncRequest),
+    Resync(SyncRequest),
+    UnregisterPlugin(UnregisterRequest),
+    TokenUpdated(SyncRequest),
+}
+
+impl Event {
+    pub fn decode(key: &str, payload: &[u8]) -> Result<Option<Event>> {
+        let event = match key {
+            REGISTER_PLUGIN => Event::RegisterPlugin(parse(key, payload)?),
+            SYNC => Event::Sync(parse(key, payload)?),
+            RESYNC => Event::Resync(parse(key, payload)?),
+            UNREGISTER_PLUGIN => Event::UnregisterPlugin(parse(key, payload)?),
+            TOKEN_UPDATED => Event::TokenUpdated(parse(key, payload)?),
+            _ => return Ok(None),
+        };
+
+        Ok(Some(event))
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Event::RegisterPlugin(_) => REGISTER_PLUGIN,
+            Event::Sync(_) => SYNC,
+            Event::Resync(_) => RESYNC,
+            Event::UnregisterPlugin(_) => UNREGISTER_PLUGIN,
+            Event::TokenUpdated(_) => TOKEN_UPDATED,
+        }
+    }
+
+    pub fn user_id(&self) -> &str {
+        match self {
+            Event::RegisterPlugin(request)
+            | Event::Sync(request)
+            | Event::Resync(request)
+            | Event::TokenUpdated(request) => &request.user_id,
+            Event::UnregisterPlugin(request) => &request.user_id,
+        }
+    }
+
+    pub fn username(&self) -> &str {
+        match self {
+            Event::RegisterPlugin(request)
+            | Event::Sync(request)
+            | Event::Resync(request)
+            | Event::TokenUpdated(request) => &request.username,
+            Event::UnregisterPlugin(request) => &request.username,
+        }
+    }
+}
+
+fn parse<'a, T: Deserialize<'a>>(key: &str, payload: &'a [u8]) -> Result<T> {
+    serde_json::from_slice(payload).context(format!("Decoding the {} payload", key))
+}
@@ -0,0 +1,363 @@
+use crate::{run, unregister, GithubApi, GithubOptions, SyncOptions, SyncReport};
+use anyhow::{anyhow, Context, Result};
+use async_std::task;
+use dead_letter::DeadLetter;
+use events::{Event, SyncRequest};
+use kafka::consumer::{Consumer, FetchOffset, Message};
+use kafka::producer::Producer;
+use log::{debug, info, warn};
+use offsets::Offsets;
+use pool::{Finished, SyncContext, SyncPool};
+use sqlx::PgPool;
+use std::collections::VecDeque;
+use std::time::Duration;
+use structopt::StructOpt;
+use sync_events::{SyncCompleted, SyncFailed};
+
+pub mod dead_letter;
+pub mod events;
+pub mod offsets;
+pub mod pool;
+pub mod sync_events;
+
+#[derive(StructOpt, Debug, Clone)]
+pub struct QueueOptions {
+    #[structopt(long, env = "QUEUE_URL")]
+    pub queue_url: String,
+    #[structopt(long, env = "QUEUE_TOPIC")]
+    pub queue_topic: String,
+    #[structopt(long, env = "QUEUE_GROUP")]
+    pub queue_group: String,
+    #[structopt(
+        long = "queue-reconnect-delay",
+        env = "QUEUE_RECONNECT_DELAY_SECS",
+        default_value = "5"
+    )]
+    pub reconnect_delay: u64,
+    #[structopt(
+        long = "queue-dead-letter-topic",
+        env = "QUEUE_DEAD_LETTER_TOPIC",
+        default_value = "github-dead-letters"
+    )]
+    pub dead_letter_topic: String,
+    #[structopt(
+        name = "queue-max-attempts",
+        long = "queue-max-attempts",
+        env = "QUEUE_MAX_ATTEMPTS",
+        default_value = "3"
+    )]
+    pub max_attempts: u32,
+    #[structopt(
+        long = "queue-output-topic",
+        env = "QUEUE_OUTPUT_TOPIC",
+        default_value = "github-sync-events"
+    )]
+    pub output_topic: String,
+    #[structopt(long = "queue-workers", env = "QUEUE_WORKERS", default_value = "4")]
+    pub workers: usize,
+    #[structopt(
+        long = "queue-retry-delay",
+        env = "QUEUE_RETRY_DELAY_SECS",
+        default_value = "5"
+    )]
+    pub retry_delay: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct Delivery {
+    pub topic: String,
+    pub partition: i32,
+    pub offset: i64,
+    pub key: Vec<u8>,
+    pub value: Vec<u8>,
+}
+
+impl Delivery {
+    fn new(topic: &str, partition: i32, m: &Message<'_>) -> Delivery {
+        Delivery {
+            topic: topic.to_string(),
+            partition,
+            offset: m.offset,
+            key: m.key.to_vec(),
+            value: m.value.to_vec(),
+        }
+    }
+}
+
+pub async fn consume(
+    db_pool: &PgPool,
+    queue_options: &QueueOptions,
+    github_options: &GithubOptions,
+    sync_options: &SyncOptions,
+) -> Result<()> {
+    debug!("Connecting to a kafka broker....");
+    debug!("[Queue URL]: {}", queue_options.queue_url);
+    debug!("[Queue Topic]: {}", queue_options.queue_topic);
+    debug!("[Queue Consumer Group]: {}", queue_options.queue_group);
+
+    let reconnect_delay = Duration::from_secs(queue_options.reconnect_delay);
+    let mut pool = SyncPool::new(
+        SyncContext {
+            db_pool: db_pool.clone(),
+            github: GithubApi::new(github_options)?,
+            sync_options: sync_options.clone(),
+            max_attempts: queue_options.max_attempts.max(1),
+            retry_delay: Duration::from_secs(queue_options.retry_delay),
+        },
+        queue_options.workers,
+    );
+    let mut offsets = Offsets::new();
+    let mut unreported = VecDeque::new();
+
+    loop {
+        let (mut consumer, mut producer) = match connect(queue_options) {
+            Ok(connection) => connection,
+            Err(e) => {
+                warn!(
+                    "Could not connect to the kafka broker, retrying in {}s: {:#}",
+                    reconnect_delay.as_secs(),
+                    e
+                );
+                task::sleep(reconnect_delay).await;
+                continue;
+            }
+        };
+
+        if let Err(e) = consume_until_disconnected(
+            &mut consumer,
+            &mut producer,
+            &mut pool,
+            &mut offsets,
+            &mut unreported,
+            queue_options,
+        )
+        .await
+        {
+            warn!(
+                "Lost the connection to the kafka broker, reconnecting in {}s: {:#}",
+                reconnect_delay.as_secs(),
+                e
+            );
+        }
+
+        offsets.reconnected();
+        task::sleep(reconnect_delay).await;
+    }
+}
+
+fn connect(queue_options: &QueueOptions) -> Result<(Consumer, Producer)> {
+    let consumer = Consumer::from_hosts(vec![queue_options.queue_url.clone()])
+        .with_topic(queue_options.queue_topic.clone())
+        .with_fallback_offset(FetchOffset::Earliest)
+        .with_group(queue_options.queue_group.clone())
+        .create()
+        .map_err(|e| anyhow!("Creating the kafka consumer: {}", e))?;
+    let producer = Producer::from_hosts(vec![queue_options.queue_url.clone()])
+        .create()
+        .map_err(|e| anyhow!("Creating the kafka producer: {}", e))?;
+
+    Ok((consumer, producer))
+}
+
+pub fn replay_dead_letters(queue_options: &QueueOptions) -> Result<usize> {
+    dead_letter::replay(
+        &queue_options.queue_url,
+        &queue_options.dead_letter_topic,
+        &format!("{}-dead-letter-replay", queue_options.queue_group),
+        &queue_options.queue_topic,
+    )
+}
+
+async fn consume_until_disconnected(
+    consumer: &mut Consumer,
+    producer: &mut Producer,
+    pool: &mut SyncPool,
+    offsets: &mut Offsets,
+    unreported: &mut VecDeque<Finished>,
+    queue_options: &QueueOptions,
+) -> Result<()> {
+    loop {
+        if !pool.is_full() {
+            let message_sets = consumer
+                .poll()
+                .map_err(|e| anyhow!("Polling the kafka broker: {}", e))?;
+
+            for ms in message_sets.iter() {
+                for m in ms.messages() {
+                    let delivery = Delivery::new(ms.topic(), ms.partition(), m);
+
+                    if !offsets.track(&delivery) {
+                        debug!(
+                            "Skipping the redelivered event at {}:{}@{}",
+                            delivery.topic, delivery.partition, delivery.offset
+                        );
+                        continue;
+                    }
+
+                    match decode_event(&delivery) {
+                        Some(event) => pool.submit(delivery, event),
+                        None => offsets.complete(&delivery),
+                    }
+                }
+            }
+        }
+
+        let mut next = if pool.is_full() {
+            pool.next_finished().await
+        } else {
+            pool.try_next_finished()
+        };
+
+        while let Some(finished) = next {
+            unreported.push_back(finished);
+            next = pool.try_next_finished();
+        }
+
+        while let Some(finished) = unreported.front() {
+            report(producer, queue_options, finished)?;
+            offsets.complete(&finished.delivery);
+            unreported.pop_front();
+        }
+
+        commit(consumer, offsets)?;
+    }
+}
+
+fn report(
+    producer: &mut Producer,
+    queue_options: &QueueOptions,
+    finished: &Finished,
+) -> Result<()> {
+    match &finished.result {
+        Ok(Some(report)) => SyncCompleted::new(finished.event.user_id(), report)
+            .publish(producer, &queue_options.output_topic),
+        Ok(None) => Ok(()),
+        Err(e) => {
+            warn!(
+                "Giving up on the event at {}:{}@{} after {} attempts: {:#}",
+                finished.delivery.topic,
+                finished.delivery.partition,
+                finished.delivery.offset,
+                finished.attempts,
+                e
+            );
+            DeadLetter::new(&finished.delivery, e, finished.attempts)
+                .publish(producer, &queue_options.dead_letter_topic)?;
+            SyncFailed::new(
+                finished.event.user_id(),
+                e,
+                finished.attempts,
+                finished.elapsed,
+            )
+            .publish(producer, &queue_options.output_topic)
+        }
+    }
+}
+
+fn commit(consumer: &mut Consumer, offsets: &mut Offsets) -> Result<()> {
+    let committable = offsets.committable();
+
+    if committable.is_empty() {
+        return Ok(());
+    }
+
+    for (topic, partition, offset) in &committable {
+        consumer
+            .consume_message(topic, *partition, *offset)
+            .map_err(|e| anyhow!("Marking the event as consumed: {}", e))?;
+    }
+
+    consumer
+        .commit_consumed()
+        .map_err(|e| anyhow!("Committing the consumed offsets: {}", e))?;
+
+    for (topic, partition, offset) in &committable {
+        offsets.committed(topic, *partition, *offset);
+    }
+
+    Ok(())
+}
+
+fn decode_event(delivery: &Delivery) -> Option<Event> {
+    let key = match std::str::from_utf8(&delivery.key) {
+        Ok(key) => key,
+        Err(e) => {
+            warn!(
+                "Skipping the event at offset {}: bad key: {}",
+                delivery.offset, e
+            );
+            return None;
+        }
+    };
+
+    match Event::decode(key, &delivery.value) {
+        Ok(Some(event)) => Some(event),
+        Ok(None) => {
+            info!(
+                "Skipping the unknown {} event at offset {}",
+                key, delivery.offset
+            );
+            None
+        }
+        Err(e) => {
+            warn!("Skipping the event at offset {}: {:#}", delivery.offset, e);
+            None
+        }
+    }
+}
+
+async fn handle_event(
+    event: &Event,
+    db_pool: &PgPool,
+    github: &GithubApi,
+    sync_options: &SyncOptions,
+) -> Result<Option<SyncReport>> {
+    debug!(
+        "Received a {} event for the user => {}",
+        event.key(),
+        event.username()
+    );
+
+    match event {
+        Event::RegisterPlugin(request) | Event::Sync(request) | Event::TokenUpdated(request) => {
+            sync_user(request, db_pool, github, sync_options)
+                .await
+                .map(Some)
+        }
+        Event::Resync(request) => {
+            let sync_options = SyncOptions {
+                full_resync: true,
+                ..sync_options.clone()
+            };
+
+            sync_user(request, db_pool, github, &sync_options)
+                .await
+                .map(Some)
+        }
+        Event::UnregisterPlugin(request) => {
+            unregister(db_pool, &request.user_id, &request.username)
+                .await
+                .context(format!("Deleting the data of {}", request.username))?;
+
+            Ok(None)
+        }
+    }
+}
+
+async fn sync_user(
+    request: &SyncRequest,
+    db_pool: &PgPool,
+    github: &GithubApi,
+    sync_options: &SyncOptions,
+) -> Result<SyncReport> {
+    run(
+        db_pool,
+        github,
+        sync_options,
+        request.user_id.clone(),
+        request.username.clone(),
+        request.token.clone(),
+    )
+    .await
+    .context(format!("Syncing the user {}", request.username))
+}
@@ -0,0 +1,192 @@
+use crate::queue::Delivery;
+use std::collections::BTreeMap;
+
+#[derive(Default)]
+struct Partition {
+    pending: BTreeMap<i64, bool>,
+    last_seen: Option<i64>,
+    last_done: Option<i64>,
+    last_committed: Option<i64>,
+}
+
+#[derive(Default)]
+pub struct Offsets {
+    partitions: BTreeMap<(String, i32), Partition>,
+}
+
+impl Offsets {
+    pub fn new() -> Offsets {
+        Offsets::default()
+    }
+
+    pub fn track(&mut self, delivery: &Delivery) -> bool {
+        let partition = self
+            .partitions
+            .entry((delivery.topic.clone(), delivery.partition))
+            .or_default();
+
+        let handled = partition.last_seen.max(partition.last_done);
+
+        if handled.is_some_and(|handled| delivery.offset <= handled)
+            || partition.pending.contains_key(&delivery.offset)
+        {
+            return false;
+        }
+
+        partition.pending.insert(delivery.offset, false);
+        partition.last_seen = Some(delivery.offset);
+
+        true
+    }
+
+    pub fn reconnected(&mut self) {
+        for partition in self.partitions.values_mut() {
+            partition.last_seen = partition.last_committed;
+        }
+    }
+
+    pub fn complete(&mut self, delivery: &Delivery) {
+        if let Some(partition) = self
+            .partitions
+            .get_mut(&(delivery.topic.clone(), delivery.partition))
+        {
+            if let Some(done) = partition.pending.get_mut(&delivery.offset) {
+                *done = true;
+            }
+        }
+    }
+
+    pub fn committable(&mut self) -> Vec<(String, i32, i64)> {
+        let mut committable = Vec::new();
+
+        for ((topic, partition_id), partition) in self.partitions.iter_mut() {
+            while let Some((&offset, &done)) = partition.pending.iter().next() {
+                if !done {
+                    break;
+                }
+
+                partition.pending.remove(&offset);
+                partition.last_done = Some(offset);
+            }
+
+            if let Some(offset) = partition.last_done {
+                if partition.last_committed < Some(offset) {
+                    committable.push((topic.clone(), *partition_id, offset));
+                }
+            }
+        }
+
+        committable
+    }
+
+    pub fn committed(&mut self, topic: &str, partition: i32, offset: i64) {
+        if let Some(partition) = self.partitions.get_mut(&(topic.to_string(), partition)) {
+            partition.last_committed = Some(offset);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delivery(partition: i32, offset: i64) -> Delivery {
+        Delivery {
+            topic: "users".into(),
+            partition,
+            offset,
+            key: Vec::new(),
+            value: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn commits_only_the_completed_prefix() {
+        let mut offsets = Offsets::new();
+        for offset in 0..4 {
+            offsets.track(&delivery(0, offset));
+        }
+
+        offsets.complete(&delivery(0, 1));
+        offsets.complete(&delivery(0, 3));
+        assert!(offsets.committable().is_empty());
+
+        offsets.complete(&delivery(0, 0));
+        assert_eq!(offsets.committable(), vec![("users".into(), 0, 1)]);
+
+        offsets.complete(&delivery(0, 2));
+        assert_eq!(offsets.committable(), vec![("users".into(), 0, 3)]);
+    }
+
+    #[test]
+    fn partitions_are_committed_independently() {
+        let mut offsets = Offsets::new();
+        offsets.track(&delivery(0, 10));
+        offsets.track(&delivery(1, 20));
+        offsets.track(&delivery(1, 21));
+
+        offsets.complete(&delivery(1, 20));
+        assert_eq!(offsets.committable(), vec![("users".into(), 1, 20)]);
+    }
+
+    #[test]
+    fn repeats_offsets_until_they_are_committed() {
+        let mut offsets = Offsets::new();
+        offsets.track(&delivery(0, 0));
+        offsets.complete(&delivery(0, 0));
+
+        assert_eq!(offsets.committable(), vec![("users".into(), 0, 0)]);
+        assert_eq!(offsets.committable(), vec![("users".into(), 0, 0)]);
+
+        offsets.committed("users", 0, 0);
+        assert!(offsets.committable().is_empty());
+    }
+
+    #[test]
+    fn skips_redelivered_offsets() {
+        let mut offsets = Offsets::new();
+        assert!(offsets.track(&delivery(0, 0)));
+        assert!(offsets.track(&delivery(0, 1)));
+        offsets.complete(&delivery(0, 0));
+        offsets.committable();
+
+        assert!(!offsets.track(&delivery(0, 0)));
+        assert!(!offsets.track(&delivery(0, 1)));
+        assert!(offsets.track(&delivery(0, 2)));
+    }
+
+    #[test]
+    fn moves_on_once_an_unreported_offset_completes() {
+        let mut offsets = Offsets::new();
+        for offset in 0..3 {
+            offsets.track(&delivery(0, offset));
+        }
+        offsets.complete(&delivery(0, 0));
+        offsets.committed("users", 0, 0);
+        offsets.complete(&delivery(0, 2));
+
+        offsets.reconnected();
+        assert!(!offsets.track(&delivery(0, 0)));
+        assert!(!offsets.track(&delivery(0, 1)));
+        assert!(!offsets.track(&delivery(0, 2)));
+        assert!(offsets.track(&delivery(0, 3)));
+        assert!(offsets.committable().is_empty());
+
+        offsets.complete(&delivery(0, 1));
+        assert_eq!(offsets.committable(), vec![("users".into(), 0, 2)]);
+    }
+
+    #[test]
+    fn accepts_untracked_offsets_after_reconnecting() {
+        let mut offsets = Offsets::new();
+        offsets.track(&delivery(0, 0));
+        offsets.complete(&delivery(0, 0));
+        offsets.committed("users", 0, 0);
+        offsets.track(&delivery(0, 5));
+
+        assert!(!offsets.track(&delivery(0, 3)));
+        offsets.reconnected();
+        assert!(offsets.track(&delivery(0, 3)));
+        assert!(!offsets.track(&delivery(0, 5)));
+    }
+}
@@ -0,0 +1,235 @@
+use crate::github::GithubError;
+use crate::queue::events::Event;
+use crate::queue::{handle_event, Delivery};
+use crate::{GithubApi, SyncOptions, SyncReport};
+use anyhow::Result;
+use async_std::task::{self, JoinHandle};
+use futures::stream::{FuturesUnordered, StreamExt};
+use futures::FutureExt;
+use log::{debug, error};
+use sqlx::PgPool;
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+pub struct SyncContext {
+    pub db_pool: PgPool,
+    pub github: GithubApi,
+    pub sync_options: SyncOptions,
+    pub max_attempts: u32,
+    pub retry_delay: Duration,
+}
+
+pub struct Finished {
+    pub delivery: Delivery,
+    pub event: Event,
+    pub result: Result<Option<SyncReport>>,
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+pub struct SyncPool {
+    context: Arc<SyncContext>,
+    workers: usize,
+    running: FuturesUnordered<JoinHandle<Finished>>,
+    waiting: VecDeque<(Delivery, Event)>,
+    busy_users: HashSet<String>,
+}
+
+impl SyncPool {
+    pub fn new(context: SyncContext, workers: usize) -> SyncPool {
+        SyncPool {
+            context: Arc::new(context),
+            workers: workers.max(1),
+            running: FuturesUnordered::new(),
+            waiting: VecDeque::new(),
+            busy_users: HashSet::new(),
+        }
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.waiting.len() >= self.workers
+    }
+
+    pub fn submit(&mut self, delivery: Delivery, event: Event) {
+        self.waiting.push_back((delivery, event));
+        self.dispatch();
+    }
+
+    pub async fn next_finished(&mut self) -> Option<Finished> {
+        let finished = self.running.next().await;
+        self.release(finished)
+    }
+
+    pub fn try_next_finished(&mut self) -> Option<Finished> {
+        let finished = self.running.next().now_or_never().flatten();
+        self.release(finished)
+    }
+
+    fn release(&mut self, finished: Option<Finished>) -> Option<Finished> {
+        if let Some(finished) = &finished {
+            self.busy_users.remove(finished.event.user_id());
+            self.dispatch();
+        }
+
+        finished
+    }
+
+    fn dispatch(&mut self) {
+        let free = self.workers.saturating_sub(self.running.len());
+        let ready = dispatchable(&self.waiting, &self.busy_users, free);
+
+        for index in ready.into_iter().rev() {
+            let (delivery, event) = self.waiting.remove(index).unwrap();
+            debug!(
+                "Dispatching the {} event at {}:{}@{} for the user => {}",
+                event.key(),
+                delivery.topic,
+                delivery.partition,
+                delivery.offset,
+                event.username()
+            );
+            self.busy_users.insert(event.user_id().to_string());
+            self.running
+                .push(task::spawn(process(self.context.clone(), delivery, event)));
+        }
+    }
+}
+
+fn dispatchable(
+    waiting: &VecDeque<(Delivery, Event)>,
+    busy_users: &HashSet<String>,
+    free: usize,
+) -> Vec<usize> {
+    let mut claimed: HashSet<&str> = busy_users.iter().map(String::as_str).collect();
+    let mut ready = Vec::new();
+
+    for (index, (_, event)) in waiting.iter().enumerate() {
+        if ready.len() >= free {
+            break;
+        }
+
+        if claimed.insert(event.user_id()) {
+            ready.push(index);
+        }
+    }
+
+    ready
+}
+
+async fn process(context: Arc<SyncContext>, delivery: Delivery, event: Event) -> Finished {
+    let started = Instant::now();
+    let mut attempt = 1;
+
+    loop {
+        let result = handle_event(
+            &event,
+            &context.db_pool,
+            &context.github,
+            &context.sync_options,
+        )
+        .await;
+
+        match result {
+            Err(e) if attempt < context.max_attempts && !is_fatal(&e) => {
+                error!(
+                    "Attempt {} of {} to process the event at {}:{}@{} failed, retrying in {}s: {:#}",
+                    attempt,
+                    context.max_attempts,
+                    delivery.topic,
+                    delivery.partition,
+                    delivery.offset,
+                    context.retry_delay.as_secs(),
+                    e
+                );
+                task::sleep(context.retry_delay).await;
+                attempt += 1;
+            }
+            result => {
+                return Finished {
+                    delivery,
+                    event,
+                    result,
+                    attempts: attempt,
+                    elapsed: started.elapsed(),
+                }
+            }
+        }
+    }
+}
+
+fn is_fatal(err: &anyhow::Error) -> bool {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<GithubError>())
+        .is_some_and(|github_error| !github_error.is_retryable())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::queue::events::SyncRequest;
+
+    fn waiting(user_ids: &[&str]) -> VecDeque<(Delivery, Event)> {
+        user_ids
+            .iter()
+            .enumerate()
+            .map(|(offset, user_id)| {
+                (
+                    Delivery {
+                        topic: "users".into(),
+                        partition: 0,
+                        offset: offset as i64,
+                        key: Vec::new(),
+                        value: Vec::new(),
+                    },
+                    Event::Sync(SyncRequest {
+                        user_id: user_id.to_string(),
+                        token: "token".into(),
+                        username: user_id.to_string(),
+                    }),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn never_dispatches_two_events_of_the_same_user() {
+        let waiting = waiting(&["alice", "alice", "bob", "alice", "carol"]);
+
+        assert_eq!(dispatchable(&waiting, &HashSet::new(), 4), vec![0, 2, 4]);
+    }
+
+    #[test]
+    fn holds_back_users_that_are_already_syncing() {
+        let waiting = waiting(&["alice", "bob", "bob"]);
+        let busy_users = vec!["bob".to_string()].into_iter().collect();
+
+        assert_eq!(dispatchable(&waiting, &busy_users, 4), vec![0]);
+    }
+
+    #[test]
+    fn fills_only_the_free_workers_in_order() {
+        let waiting = waiting(&["alice", "bob", "carol"]);
+
+        assert_eq!(dispatchable(&waiting, &HashSet::new(), 2), vec![0, 1]);
+        assert!(dispatchable(&waiting, &HashSet::new(), 0).is_empty());
+    }
+
+    #[test]
+    fn gives_up_on_fatal_github_errors() {
+        let unauthorized = anyhow::Error::new(GithubError::Http {
+            status: 401,
+            body: "Bad credentials".into(),
+        })
+        .context("Syncing the user alice");
+        let bad_gateway = anyhow::Error::new(GithubError::Http {
+            status: 502,
+            body: String::new(),
+        })
+        .context("Syncing the user alice");
+
+        assert!(is_fatal(&unauthorized));
+        assert!(!is_fatal(&bad_gateway));
+        assert!(!is_fatal(&anyhow::anyhow!("The database went away")));
+    }
+}
@@ -0,0 +1,82 @@
+use crate::queue::dead_letter::error_kind;
+use crate::store::StoreSummary;
+use crate::SyncReport;
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Utc};
+use kafka::producer::{Producer, Record};
+use log::info;
+use serde::Serialize;
+use std::time::Duration;
+
+pub const SYNC_COMPLETED: &str = "SYNC_COMPLETED:GITHUB";
+pub const SYNC_FAILED: &str = "SYNC_FAILED:GITHUB";
+
+#[derive(Serialize, Debug)]
+pub struct SyncCompleted<'a> {
+    pub user_id: &'a str,
+    #[serde(flatten)]
+    pub summary: StoreSummary,
+    pub duration_ms: u128,
+    pub window_from: Option<DateTime<Utc>>,
+    pub window_to: Option<DateTime<Utc>>,
+}
+
+impl<'a> SyncCompleted<'a> {
+    pub fn new(user_id: &'a str, report: &SyncReport) -> SyncCompleted<'a> {
+        SyncCompleted {
+            user_id,
+            summary: report.summary,
+            duration_ms: report.duration.as_millis(),
+            window_from: report.window.and_then(|window| window.from),
+            window_to: report.window.map(|window| window.to),
+        }
+    }
+
+    pub fn publish(&self, producer: &mut Producer, topic: &str) -> Result<()> {
+        publish(producer, topic, SYNC_COMPLETED, self)?;
+        info!("Published {} for the user {}", SYNC_COMPLETED, self.user_id);
+
+        Ok(())
+    }
+}
+
+#[derive(Serialize, Debug)]
+pub struct SyncFailed<'a> {
+    pub user_id: &'a str,
+    pub error_kind: &'static str,
+    pub error: String,
+    pub attempts: u32,
+    pub duration_ms: u128,
+}
+
+impl<'a> SyncFailed<'a> {
+    pub fn new(
+        user_id: &'a str,
+        err: &anyhow::Error,
+        attempts: u32,
+        duration: Duration,
+    ) -> SyncFailed<'a> {
+        SyncFailed {
+            user_id,
+            error_kind: error_kind(err),
+            error: format!("{:#}", err),
+            attempts,
+            duration_ms: duration.as_millis(),
+        }
+    }
+
+    pub fn publish(&self, producer: &mut Producer, topic: &str) -> Result<()> {
+        publish(producer, topic, SYNC_FAILED, self)?;
+        info!("Published {} for the user {}", SYNC_FAILED, self.user_id);
+
+        Ok(())
+    }
+}
+
+fn publish<T: Serialize>(producer: &mut Producer, topic: &str, key: &str, event: &T) -> Result<()> {
+    let value = serde_json::to_vec(event)?;
+
+    producer
+        .send(&Record::from_key_value(topic, key, value))
+        .map_err(|e| anyhow!("Publishing {} to {}: {}", key, topic, e))
+}
@@ -1,45 +1,227 @@
-use crate::database::repository::{CommitRepository, PullRequestRepository, ReviewRepository};
-use crate::dto::pull_requests::PullRequestsDTO;
+use crate::database::repository::{
+    CommitRepository, ContributionDayRepository, GithubAccountRepository, PullRequestRepository,
+    ReviewRepository, SyncStateRepository, UpsertCounts, UserContributionRepository,
+};
+use crate::dto::pull_requests::{Commit, PullRequest, PullRequestsDTO, Review};
+use crate::dto::{ContributionCalendarDTO, ContributionType};
 use anyhow::*;
+use chrono::{DateTime, Utc};
+use log::info;
+use serde::Serialize;
 use sqlx::PgPool;
+use std::collections::HashSet;
+
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StoreSummary {
+    pub pull_requests: UpsertCounts,
+    pub reviews: UpsertCounts,
+    pub commits: UpsertCounts,
+    pub contribution_days: UpsertCounts,
+}
+
+impl StoreSummary {
+    pub fn add(&mut self, other: &StoreSummary) {
+        self.pull_requests.add(&other.pull_requests);
+        self.reviews.add(&other.reviews);
+        self.commits.add(&other.commits);
+        self.contribution_days.add(&other.contribution_days);
+    }
+}
 
 pub struct Store {}
 
 impl Store {
-    pub async fn store_pull_requests(
+    pub async fn load_pull_requests_dto(
         db_pool: &PgPool,
-        pull_requests_dto: &PullRequestsDTO,
-    ) -> Result<()> {
-        for pull_request in pull_requests_dto.pull_requests.iter() {
-            let _ = PullRequestRepository::create(db_pool, pull_request.1).await?;
+        user_id: &str,
+        username: &str,
+    ) -> Result<PullRequestsDTO> {
+        let mut pull_requests_dto = PullRequestsDTO::new();
+        pull_requests_dto.user = GithubAccountRepository::find(db_pool, user_id).await?;
+
+        for pull_request in PullRequestRepository::find_for_user(db_pool, user_id, username).await?
+        {
+            pull_requests_dto.add_pull_request(pull_request);
         }
 
-        Ok(())
+        let pull_request_ids: Vec<String> =
+            pull_requests_dto.pull_requests.keys().cloned().collect();
+
+        for (pull_request_id, review) in
+            ReviewRepository::find_by_pull_request_ids(db_pool, &pull_request_ids).await?
+        {
+            pull_requests_dto.add_review(&pull_request_id, review);
+        }
+
+        for (pull_request_id, commit) in
+            CommitRepository::find_by_pull_request_ids(db_pool, &pull_request_ids).await?
+        {
+            pull_requests_dto.add_commit(&pull_request_id, commit);
+        }
+
+        for (pull_request_id, contribution_type) in
+            UserContributionRepository::find_for_user(db_pool, user_id).await?
+        {
+            pull_requests_dto.add_contribution(&pull_request_id, contribution_type);
+        }
+
+        info!(
+            "Loaded {} stored pull requests for {}",
+            pull_requests_dto.pull_requests.len(),
+            username
+        );
+
+        Ok(pull_requests_dto)
     }
 
-    pub async fn store_reviews(
+    pub async fn store_pull_requests_dto(
         db_pool: &PgPool,
         pull_requests_dto: &PullRequestsDTO,
-    ) -> Result<()> {
-        for (_, pull_request) in pull_requests_dto.pull_requests.iter() {
-            for review in &pull_request.reviews {
-                ReviewRepository::create(db_pool, &pull_request, review).await?;
+        user_id: &str,
+        username: &str,
+        sync_progress: &[(ContributionType, DateTime<Utc>)],
+        chunk_size: usize,
+    ) -> Result<StoreSummary> {
+        let pull_requests: Vec<&PullRequest> = pull_requests_dto.pull_requests.values().collect();
+
+        let reviews = unique_reviews(&pull_requests);
+        let commits = unique_commits(&pull_requests);
+
+        let contributions: Vec<&(String, ContributionType)> = pull_requests_dto
+            .contributions
+            .iter()
+            .filter(|(pull_request_id, _)| {
+                pull_requests_dto
+                    .pull_requests
+                    .contains_key(pull_request_id)
+            })
+            .collect();
+
+        let mut tx = db_pool.begin().await?;
+
+        match &pull_requests_dto.user {
+            Some(user) => {
+                GithubAccountRepository::upsert(
+                    &mut tx,
+                    user_id,
+                    user.login.as_deref().unwrap_or(username),
+                    user.id.as_deref(),
+                )
+                .await?
             }
+            None => GithubAccountRepository::upsert(&mut tx, user_id, username, None).await?,
         }
 
-        Ok(())
+        let summary = StoreSummary {
+            pull_requests: PullRequestRepository::upsert_many(&mut tx, &pull_requests, chunk_size)
+                .await
+                .context("Storing the pull requests")?,
+            reviews: ReviewRepository::upsert_many(&mut tx, &reviews, chunk_size)
+                .await
+                .context("Storing the reviews")?,
+            commits: CommitRepository::upsert_many(&mut tx, &commits, chunk_size)
+                .await
+                .context("Storing the commits")?,
+            contribution_days: UpsertCounts::default(),
+        };
+
+        UserContributionRepository::insert_many(&mut tx, user_id, &contributions, chunk_size)
+            .await
+            .context("Linking the contributions to the user")?;
+
+        for (contribution_type, synced_at) in sync_progress {
+            SyncStateRepository::record(&mut tx, user_id, *contribution_type, *synced_at).await?;
+        }
+
+        tx.commit().await?;
+
+        for (kind, counts) in &[
+            ("pull requests", summary.pull_requests),
+            ("reviews", summary.reviews),
+            ("commits", summary.commits),
+        ] {
+            info!(
+                "Stored the {} for {}: {} inserted, {} updated, {} unchanged",
+                kind, username, counts.inserted, counts.updated, counts.unchanged
+            );
+        }
+
+        Ok(summary)
     }
 
-    pub async fn store_commits(
+    pub async fn store_contribution_calendar(
         db_pool: &PgPool,
-        pull_requests_dto: &PullRequestsDTO,
-    ) -> Result<()> {
-        for (_, pull_request) in pull_requests_dto.pull_requests.iter() {
-            for commit in &pull_request.commits {
-                CommitRepository::create(db_pool, &pull_request, commit).await?;
-            }
-        }
+        contribution_calendar: &ContributionCalendarDTO,
+        user_id: &str,
+        username: &str,
+        chunk_size: usize,
+    ) -> Result<UpsertCounts> {
+        let mut tx = db_pool.begin().await?;
+
+        GithubAccountRepository::upsert(&mut tx, user_id, username, None).await?;
+        let counts = ContributionDayRepository::upsert_many(
+            &mut tx,
+            user_id,
+            &contribution_calendar.days,
+            chunk_size,
+        )
+        .await
+        .context("Storing the contribution days")?;
+
+        tx.commit().await?;
+
+        info!(
+            "Stored the contribution days for {}: {} inserted, {} updated, {} unchanged",
+            username, counts.inserted, counts.updated, counts.unchanged
+        );
+
+        Ok(counts)
+    }
+
+    pub async fn delete_user_data(db_pool: &PgPool, user_id: &str, username: &str) -> Result<()> {
+        let mut tx = db_pool.begin().await?;
+
+        let pull_requests =
+            PullRequestRepository::delete_for_user(&mut tx, user_id, username).await?;
+        GithubAccountRepository::delete(&mut tx, user_id).await?;
+
+        tx.commit().await?;
+
+        info!(
+            "Deleted the data for {}: {} pull requests with their reviews and commits",
+            username, pull_requests
+        );
 
         Ok(())
     }
 }
+
+pub fn unique_reviews<'a>(pull_requests: &[&'a PullRequest]) -> Vec<(&'a PullRequest, &'a Review)> {
+    let mut review_ids = HashSet::new();
+
+    pull_requests
+        .iter()
+        .flat_map(|pull_request| {
+            pull_request
+                .reviews
+                .iter()
+                .map(move |review| (*pull_request, review))
+        })
+        .filter(|(_, review)| review_ids.insert(review.id.as_str()))
+        .collect()
+}
+
+pub fn unique_commits<'a>(pull_requests: &[&'a PullRequest]) -> Vec<(&'a PullRequest, &'a Commit)> {
+    let mut commit_ids = HashSet::new();
+
+    pull_requests
+        .iter()
+        .flat_map(|pull_request| {
+            pull_request
+                .commits
+                .iter()
+                .map(move |commit| (*pull_request, commit))
+        })
+        .filter(|(_, commit)| commit_ids.insert(commit.id.as_str()))
+        .collect()
+}
@@ -1,55 +1,311 @@
-use crate::dto::PullRequestsDTO;
-use crate::github::{get_pull_request_contributions, get_pull_request_review_contributions};
-use crate::store::Store;
+use crate::database::repository::{PullRequestRepository, SyncStateRepository};
+use crate::dto::{ContributionType, PullRequestsDTO, SyncWindow};
+use crate::github::{
+    get_contribution_calendar, get_contribution_years, get_pull_request_contributions,
+    get_pull_request_review_contributions, get_pull_requests_by_id, GithubClient,
+};
+use crate::store::{Store, StoreSummary};
 use anyhow::*;
-use log::debug;
+use chrono::{DateTime, Utc};
+use log::{debug, info};
 use sqlx::PgPool;
 
 pub struct Worker<'a> {
     db_pool: &'a PgPool,
     pull_requests: Option<PullRequestsDTO>,
+    user_id: String,
+    username: String,
+    sync_progress: Vec<(ContributionType, DateTime<Utc>)>,
+    batch_size: usize,
+    summary: StoreSummary,
+    window: Option<SyncWindow>,
 }
 
 impl<'a> Worker<'a> {
-    pub fn new(db_pool: &PgPool) -> Worker {
+    pub fn new(db_pool: &PgPool, user_id: String, batch_size: usize) -> Worker<'_> {
         Worker {
             db_pool,
             pull_requests: None::<PullRequestsDTO>,
+            user_id,
+            username: String::new(),
+            sync_progress: Vec::new(),
+            batch_size,
+            summary: StoreSummary::default(),
+            window: None,
         }
     }
 
-    pub async fn fetch_data_from_github(
-        &'a mut self,
+    pub fn summary(&self) -> StoreSummary {
+        self.summary
+    }
+
+    pub fn window(&self) -> Option<SyncWindow> {
+        self.window
+    }
+
+    pub fn pull_requests(&self) -> Option<&PullRequestsDTO> {
+        self.pull_requests.as_ref()
+    }
+
+    fn record_window(&mut self, window: SyncWindow) {
+        self.window = Some(match self.window {
+            Some(current) => current.span(&window),
+            None => window,
+        });
+    }
+
+    async fn sync_window(
+        &self,
+        username: &str,
+        contribution_type: ContributionType,
+        full_resync: bool,
+    ) -> Result<Vec<SyncWindow>> {
+        let last_synced_at = if full_resync {
+            None
+        } else {
+            SyncStateRepository::last_synced_at(self.db_pool, &self.user_id, contribution_type)
+                .await?
+        };
+        let windows = SyncWindow::since(last_synced_at, Utc::now());
+
+        match last_synced_at {
+            Some(from) => debug!(
+                "Syncing {} for {} since {} in {} windows",
+                contribution_type.as_str(),
+                username,
+                from,
+                windows.len()
+            ),
+            None => debug!(
+                "Running a full sync of {} for {}",
+                contribution_type.as_str(),
+                username
+            ),
+        }
+
+        Ok(windows)
+    }
+
+    async fn fetch_windows(
+        &mut self,
+        client: &GithubClient,
         username: String,
-        token: String,
-    ) -> Result<&Worker<'a>> {
+        pull_requests_windows: &[SyncWindow],
+        reviews_windows: &[SyncWindow],
+    ) -> Result<()> {
         let mut pr_contributions = PullRequestsDTO::new();
-        get_pull_request_contributions(token.clone(), username.clone(), &mut pr_contributions)
-            .await?;
-        get_pull_request_review_contributions(
-            token.clone(),
-            username.clone(),
-            &mut pr_contributions,
-        )
-        .await?;
+        let mut review_contributions = PullRequestsDTO::new();
+
+        futures::try_join!(
+            async {
+                for window in pull_requests_windows {
+                    get_pull_request_contributions(
+                        client,
+                        username.clone(),
+                        window,
+                        &mut pr_contributions,
+                    )
+                    .await?;
+                }
+
+                Ok(())
+            },
+            async {
+                for window in reviews_windows {
+                    get_pull_request_review_contributions(
+                        client,
+                        username.clone(),
+                        window,
+                        &mut review_contributions,
+                    )
+                    .await?;
+                }
+
+                Ok(())
+            },
+        )?;
+        pr_contributions.merge(review_contributions);
         debug!(
             "Fetched {} pull request contributions for {}",
             pr_contributions.pull_requests.keys().len(),
             username
         );
 
+        if let Some(rate_limit) = client.rate_limit() {
+            info!(
+                "Github rate limit budget after syncing {}: {} points remaining, resets at {}",
+                username, rate_limit.remaining, rate_limit.reset_at
+            );
+        }
+
         self.pull_requests = Some(pr_contributions);
+        self.username = username;
+
+        Ok(())
+    }
+
+    async fn refresh_open_pull_requests(&mut self, client: &GithubClient) -> Result<()> {
+        let open_ids = PullRequestRepository::find_open_ids_for_user(
+            self.db_pool,
+            &self.user_id,
+            &self.username,
+        )
+        .await?;
+        let pull_requests = match self.pull_requests.as_mut() {
+            Some(pull_requests) => pull_requests,
+            None => return Ok(()),
+        };
+        let ids: Vec<String> = open_ids
+            .into_iter()
+            .filter(|id| !pull_requests.pull_requests.contains_key(id))
+            .collect();
+
+        if ids.is_empty() {
+            return Ok(());
+        }
+
+        info!(
+            "Refreshing {} stored open pull requests of {}",
+            ids.len(),
+            self.username
+        );
+        get_pull_requests_by_id(client, &ids, pull_requests).await?;
+
+        Ok(())
+    }
+
+    pub async fn fetch_data_from_github(
+        &mut self,
+        client: &GithubClient,
+        username: String,
+        full_resync: bool,
+    ) -> Result<&mut Worker<'a>> {
+        let pull_requests_windows = self
+            .sync_window(&username, ContributionType::PullRequests, full_resync)
+            .await?;
+        let reviews_windows = self
+            .sync_window(&username, ContributionType::PullRequestReviews, full_resync)
+            .await?;
+
+        self.fetch_windows(client, username, &pull_requests_windows, &reviews_windows)
+            .await?;
+
+        let pull_requests_window = span(&pull_requests_windows);
+        let reviews_window = span(&reviews_windows);
+
+        if pull_requests_window.from.is_some() || reviews_window.from.is_some() {
+            self.refresh_open_pull_requests(client).await?;
+        }
+
+        self.sync_progress = vec![
+            (ContributionType::PullRequests, pull_requests_window.to),
+            (ContributionType::PullRequestReviews, reviews_window.to),
+        ];
+        self.record_window(pull_requests_window.span(&reviews_window));
 
         Ok(self)
     }
 
-    pub async fn store_data(&'a self) -> Result<&Worker<'a>> {
+    pub async fn backfill(
+        &mut self,
+        client: &GithubClient,
+        username: String,
+        restart: bool,
+    ) -> Result<&mut Worker<'a>> {
+        let years = get_contribution_years(client, username.clone()).await?;
+        let resume_from = if restart {
+            None
+        } else {
+            SyncStateRepository::last_synced_at(
+                self.db_pool,
+                &self.user_id,
+                ContributionType::Backfill,
+            )
+            .await?
+        };
+        let windows = SyncWindow::backfill(&years, Utc::now());
+        let last_window = windows.len().saturating_sub(1);
+
+        info!(
+            "Backfilling {} across the contribution years {:?}",
+            username, years
+        );
+
+        for (index, window) in windows.iter().enumerate() {
+            if let Some(resume_from) = resume_from {
+                if window.to <= resume_from {
+                    continue;
+                }
+            }
+
+            info!(
+                "Backfilling {} from {} to {}",
+                username,
+                window.from.unwrap_or(window.to),
+                window.to
+            );
+            self.fetch_windows(
+                client,
+                username.clone(),
+                std::slice::from_ref(window),
+                std::slice::from_ref(window),
+            )
+            .await?;
+            self.sync_progress = vec![(ContributionType::Backfill, window.to)];
+            self.record_window(*window);
+
+            if index == last_window {
+                self.sync_progress
+                    .push((ContributionType::PullRequests, window.to));
+                self.sync_progress
+                    .push((ContributionType::PullRequestReviews, window.to));
+            }
+
+            self.store_data().await?;
+        }
+
+        Ok(self)
+    }
+
+    pub async fn sync_contribution_calendar(
+        &mut self,
+        client: &GithubClient,
+        username: String,
+    ) -> Result<&mut Worker<'a>> {
+        let contribution_calendar = get_contribution_calendar(client, username.clone()).await?;
+        let counts = Store::store_contribution_calendar(
+            self.db_pool,
+            &contribution_calendar,
+            &self.user_id,
+            &username,
+            self.batch_size,
+        )
+        .await?;
+        self.summary.contribution_days.add(&counts);
+
+        Ok(self)
+    }
+
+    pub async fn store_data(&mut self) -> Result<&mut Worker<'a>> {
         if let Some(pull_requests) = &self.pull_requests {
-            Store::store_pull_requests(self.db_pool, pull_requests).await?;
-            Store::store_reviews(self.db_pool, pull_requests).await?;
-            Store::store_commits(self.db_pool, pull_requests).await?;
+            let summary = Store::store_pull_requests_dto(
+                self.db_pool,
+                pull_requests,
+                &self.user_id,
+                &self.username,
+                &self.sync_progress,
+                self.batch_size,
+            )
+            .await?;
+            self.summary.add(&summary);
         }
 
         Ok(self)
     }
 }
+
+fn span(windows: &[SyncWindow]) -> SyncWindow {
+    windows[1..]
+        .iter()
+        .fold(windows[0], |span, window| span.span(window))
+}
@@ -0,0 +1,897 @@
+mod support;
+
+use async_std::task;
+use chrono::{Datelike, Duration, NaiveDate, Utc};
+use ghworker::dto::{AuthorKind, ContributionType, PullRequestsDTO, SyncWindow};
+use ghworker::{
+    get_contribution_calendar, get_pull_request_contributions,
+    get_pull_request_review_contributions, get_pull_requests_by_id, GithubClient, GithubError,
+    QueueOptions, SyncOptions,
+};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
+use std::collections::{BTreeSet, HashSet};
+use std::sync::{Mutex, MutexGuard};
+use structopt::StructOpt;
+use support::dataset::{FakeActor, FakeContribution, FakeUser};
+use support::server::calendar_level;
+use support::{Dataset, DatasetOptions, Failure, MockGithub, PageSizes};
+
+const LOGIN: &str = "fake-user-0";
+
+fn small_pages() -> PageSizes {
+    PageSizes {
+        contributions: 5,
+        reviews: 2,
+        commits: 3,
+        nested: 2,
+    }
+}
+
+fn all_time() -> SyncWindow {
+    SyncWindow {
+        from: None,
+        to: Utc::now(),
+    }
+}
+
+fn fetch(
+    server: &MockGithub,
+    login: &str,
+    window: &SyncWindow,
+    max_attempts: u32,
+) -> anyhow::Result<PullRequestsDTO> {
+    let client = GithubClient::new(&server.options(max_attempts), "test-token".into())?;
+    let mut pull_requests = PullRequestsDTO::new();
+    let mut review_pull_requests = PullRequestsDTO::new();
+
+    task::block_on(async {
+        get_pull_request_contributions(&client, login.into(), window, &mut pull_requests).await?;
+        get_pull_request_review_contributions(
+            &client,
+            login.into(),
+            window,
+            &mut review_pull_requests,
+        )
+        .await?;
+
+        anyhow::Ok(())
+    })?;
+    pull_requests.merge(review_pull_requests);
+
+    Ok(pull_requests)
+}
+
+fn in_window<'a>(
+    contributions: &'a [FakeContribution],
+    window: &'a SyncWindow,
+) -> impl Iterator<Item = &'a FakeContribution> {
+    let from = window
+        .from
+        .unwrap_or_else(|| window.to - Duration::days(365));
+
+    contributions.iter().filter(move |contribution| {
+        contribution.occurred_at >= from && contribution.occurred_at <= window.to
+    })
+}
+
+fn expected_contributions(
+    user: &FakeUser,
+    dataset: &Dataset,
+    window: &SyncWindow,
+) -> HashSet<(String, ContributionType)> {
+    in_window(&user.pull_request_contributions, window)
+        .map(|contribution| (contribution, ContributionType::PullRequests))
+        .chain(
+            in_window(&user.review_contributions, window)
+                .map(|contribution| (contribution, ContributionType::PullRequestReviews)),
+        )
+        .map(|(contribution, contribution_type)| {
+            (
+                dataset.pull_requests[contribution.pull_request].id.clone(),
+                contribution_type,
+            )
+        })
+        .collect()
+}
+
+fn author_kind(actor: &FakeActor) -> AuthorKind {
+    match actor {
+        FakeActor::User { .. } => AuthorKind::User,
+        FakeActor::Bot { .. } => AuthorKind::Bot,
+        FakeActor::Mannequin { .. } => AuthorKind::Mannequin,
+        FakeActor::Ghost => AuthorKind::Ghost,
+    }
+}
+
+fn assert_matches_dataset(
+    pull_requests: &PullRequestsDTO,
+    dataset: &Dataset,
+    login: &str,
+    window: &SyncWindow,
+) {
+    let user = dataset.user(login).unwrap();
+    let contributions = expected_contributions(user, dataset, window);
+    let expected_ids: BTreeSet<&str> = contributions.iter().map(|(id, _)| id.as_str()).collect();
+
+    assert_eq!(
+        pull_requests
+            .user
+            .as_ref()
+            .and_then(|user| user.id.as_deref()),
+        Some(user.id.as_str())
+    );
+    assert_eq!(pull_requests.contributions, contributions);
+    assert_eq!(
+        pull_requests
+            .pull_requests
+            .keys()
+            .map(String::as_str)
+            .collect::<BTreeSet<&str>>(),
+        expected_ids
+    );
+
+    for expected in &dataset.pull_requests {
+        let pull_request = match pull_requests.pull_requests.get(&expected.id) {
+            Some(pull_request) => pull_request,
+            None => continue,
+        };
+
+        assert_eq!(pull_request.repository, expected.repository);
+        assert_eq!(pull_request.number, expected.number);
+        assert_eq!(pull_request.merged, expected.merged_at.is_some());
+        assert_eq!(pull_request.author.kind, author_kind(&expected.author));
+        assert_eq!(
+            pull_request
+                .reviews
+                .iter()
+                .map(|review| review.id.as_str())
+                .collect::<BTreeSet<&str>>(),
+            expected
+                .published_reviews()
+                .map(|review| review.id.as_str())
+                .collect::<BTreeSet<&str>>(),
+            "reviews of {}",
+            expected.id
+        );
+        assert_eq!(
+            pull_request
+                .commits
+                .iter()
+                .map(|commit| commit.id.as_str())
+                .collect::<BTreeSet<&str>>(),
+            expected
+                .pushed_commits()
+                .map(|commit| commit.id.as_str())
+                .collect::<BTreeSet<&str>>(),
+            "commits of {}",
+            expected.id
+        );
+    }
+}
+
+fn github_error(err: &anyhow::Error) -> &GithubError {
+    err.chain()
+        .find_map(|cause| cause.downcast_ref::<GithubError>())
+        .unwrap_or_else(|| panic!("expected a github error, got {:#}", err))
+}
+
+#[test]
+fn fetches_every_page_with_small_page_sizes() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let window = all_time();
+
+    let pull_requests = fetch(&server, LOGIN, &window, 1).unwrap();
+
+    assert_matches_dataset(&pull_requests, &dataset, LOGIN, &window);
+    let user = dataset.user(LOGIN).unwrap();
+    assert_eq!(
+        server.received_count("PullRequestContributionsQuery"),
+        user.pull_request_contributions
+            .len()
+            .div_ceil(small_pages().contributions)
+    );
+    assert!(server.received_count("PullRequestReviewsQuery") > 0);
+    assert!(server.received_count("PullRequestCommitsQuery") > 0);
+}
+
+#[test]
+fn skips_sub_resource_queries_when_nothing_is_truncated() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), PageSizes::default());
+    let window = all_time();
+
+    let pull_requests = fetch(&server, LOGIN, &window, 1).unwrap();
+
+    assert_matches_dataset(&pull_requests, &dataset, LOGIN, &window);
+    assert_eq!(server.received_count("PullRequestContributionsQuery"), 1);
+    assert_eq!(
+        server.received_count("PullRequestReviewContributionsQuery"),
+        1
+    );
+    assert_eq!(server.received_count("PullRequestReviewsQuery"), 0);
+    assert_eq!(server.received_count("PullRequestCommitsQuery"), 0);
+}
+
+#[test]
+fn only_fetches_contributions_inside_the_window() {
+    let options = DatasetOptions::default();
+    let dataset = Dataset::generate(&options);
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let window = SyncWindow {
+        from: Some(options.start + Duration::days(100)),
+        to: options.start + Duration::days(300),
+    };
+
+    let pull_requests = fetch(&server, LOGIN, &window, 1).unwrap();
+
+    assert_matches_dataset(&pull_requests, &dataset, LOGIN, &window);
+    assert!(pull_requests.pull_requests.len() < dataset.pull_requests.len());
+}
+
+#[test]
+fn rejects_windows_longer_than_a_year() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    let window = SyncWindow {
+        from: Some(Utc::now() - Duration::days(400)),
+        to: Utc::now(),
+    };
+
+    let err = fetch(&server, LOGIN, &window, 3).unwrap_err();
+
+    assert!(matches!(
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<GithubError>()),
+        Some(GithubError::GraphQL(_))
+    ));
+    assert_eq!(server.received_count("PullRequestContributionsQuery"), 1);
+}
+
+#[test]
+fn shares_the_rate_limit_budget_per_token() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    let api = server.api(1);
+    let mut pull_requests = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_contributions(
+        &api.client("test-token".into()),
+        LOGIN.into(),
+        &all_time(),
+        &mut pull_requests,
+    ))
+    .unwrap();
+
+    assert!(api.client("test-token".into()).rate_limit().is_some());
+    assert!(api.client("other-token".into()).rate_limit().is_none());
+}
+
+#[test]
+fn retries_injected_server_errors() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    server.fail(
+        Some("PullRequestContributionsQuery"),
+        1,
+        Failure::Status(502),
+    );
+    server.fail(Some("PullRequestReviewsQuery"), 2, Failure::Status(503));
+    let window = all_time();
+
+    let pull_requests = fetch(&server, LOGIN, &window, 3).unwrap();
+
+    assert_matches_dataset(&pull_requests, &dataset, LOGIN, &window);
+}
+
+#[test]
+fn waits_out_rate_limits() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    server.fail(
+        Some("PullRequestContributionsQuery"),
+        1,
+        Failure::SecondaryRateLimit(1),
+    );
+    server.fail(Some("PullRequestCommitsQuery"), 1, Failure::RateLimited);
+    let window = all_time();
+
+    let pull_requests = fetch(&server, LOGIN, &window, 2).unwrap();
+
+    assert_matches_dataset(&pull_requests, &dataset, LOGIN, &window);
+}
+
+#[test]
+fn rate_limits_count_against_the_retries() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    server.fail(None, 10, Failure::SecondaryRateLimit(0));
+
+    let err = fetch(&server, LOGIN, &all_time(), 2).unwrap_err();
+
+    assert!(matches!(
+        github_error(&err),
+        GithubError::RateLimited { .. }
+    ));
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn gives_up_once_the_retries_are_spent() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    server.fail(None, 10, Failure::Status(500));
+
+    let err = fetch(&server, LOGIN, &all_time(), 2).unwrap_err();
+
+    match github_error(&err) {
+        GithubError::Http { status, .. } => assert_eq!(*status, 500),
+        err => panic!("unexpected error {}", err),
+    }
+    assert_eq!(server.received().len(), 2);
+}
+
+#[test]
+fn does_not_retry_typed_graphql_errors() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    server.fail(None, 10, Failure::GraphQL("FORBIDDEN".into()));
+
+    let err = fetch(&server, LOGIN, &all_time(), 3).unwrap_err();
+
+    assert!(matches!(github_error(&err), GithubError::GraphQL(_)));
+    assert_eq!(server.received().len(), 1);
+}
+
+#[test]
+fn rejects_malformed_responses() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+    server.fail(None, 1, Failure::Malformed);
+
+    assert!(fetch(&server, LOGIN, &all_time(), 3).is_err());
+}
+
+#[test]
+fn unknown_users_are_not_found() {
+    let server = MockGithub::start(Dataset::generate(&DatasetOptions::default()), small_pages());
+
+    let err = fetch(&server, "nobody", &all_time(), 3).unwrap_err();
+
+    match github_error(&err) {
+        GithubError::GraphQL(errors) => {
+            assert_eq!(errors[0].error_type.as_deref(), Some("NOT_FOUND"))
+        }
+        err => panic!("unexpected error {}", err),
+    }
+}
+
+#[test]
+fn fetches_the_contribution_calendar() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let client = GithubClient::new(&server.options(1), "test-token".into()).unwrap();
+
+    let calendar = task::block_on(get_contribution_calendar(&client, LOGIN.into())).unwrap();
+
+    let user = dataset.user(LOGIN).unwrap();
+    assert_eq!(calendar.days.len(), user.calendar.len());
+    assert_eq!(
+        calendar.total(),
+        user.calendar.iter().map(|day| day.count).sum::<i64>()
+    );
+    for (day, expected) in calendar.days.iter().zip(user.calendar.iter()) {
+        assert_eq!(day.date, expected.date);
+        assert_eq!(day.count as i64, expected.count);
+        assert_eq!(
+            day.weekday as u32,
+            expected.date.weekday().num_days_from_sunday()
+        );
+        assert_eq!(day.level, calendar_level(expected.count));
+    }
+}
+
+fn merge_first_open_pull_request(server: &MockGithub, user: &FakeUser) -> String {
+    let mut merged_id = None;
+
+    server.update(|dataset| {
+        let index = user
+            .pull_request_contributions
+            .iter()
+            .map(|contribution| contribution.pull_request)
+            .find(|index| dataset.pull_requests[*index].closed_at.is_none())
+            .expect("the generated user has no open pull request");
+        let pull_request = &mut dataset.pull_requests[index];
+        let merged_at = pull_request.created_at + Duration::days(2);
+
+        pull_request.closed_at = Some(merged_at);
+        pull_request.merged_at = Some(merged_at);
+        merged_id = Some(pull_request.id.clone());
+    });
+
+    merged_id.unwrap()
+}
+
+#[test]
+fn refreshes_pull_requests_by_id() {
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let client = GithubClient::new(&server.options(1), "test-token".into()).unwrap();
+    let merged_id = merge_first_open_pull_request(&server, dataset.user(LOGIN).unwrap());
+    let mut pull_requests = PullRequestsDTO::new();
+
+    task::block_on(get_pull_requests_by_id(
+        &client,
+        &[merged_id.clone(), "PR_deleted".into()],
+        &mut pull_requests,
+    ))
+    .unwrap();
+
+    assert_eq!(pull_requests.pull_requests.len(), 1);
+    let pull_request = &pull_requests.pull_requests[&merged_id];
+    assert!(pull_request.merged);
+    assert!(pull_request.date_closed.is_some());
+    assert!(pull_requests.contributions.is_empty());
+}
+
+static DATABASE: Mutex<()> = Mutex::new(());
+
+fn lock_database() -> MutexGuard<'static, ()> {
+    DATABASE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn database_url() -> String {
+    dotenv::dotenv().ok();
+    std::env::var("DATABASE_URL").expect("DATABASE_URL must point at a test database")
+}
+
+fn test_user_id(login: &str) -> String {
+    format!(
+        "e2e-{}-{}",
+        login,
+        Utc::now().timestamp_nanos_opt().unwrap()
+    )
+}
+
+async fn stored_ids(db_pool: &PgPool, query: &str, bind: Vec<String>) -> BTreeSet<String> {
+    let ids: Vec<String> = sqlx::query(query)
+        .bind(bind)
+        .map(|row: PgRow| row.get(0))
+        .fetch_all(db_pool)
+        .await
+        .unwrap();
+
+    ids.into_iter().collect()
+}
+
+async fn assert_stored(
+    db_pool: &PgPool,
+    dataset: &Dataset,
+    user_id: &str,
+    login: &str,
+    window: &SyncWindow,
+) {
+    let user = dataset.user(login).unwrap();
+    let pull_request_ids: BTreeSet<String> = expected_contributions(user, dataset, window)
+        .into_iter()
+        .map(|(id, _)| id)
+        .collect();
+    let pull_requests = dataset
+        .pull_requests
+        .iter()
+        .filter(|pull_request| pull_request_ids.contains(&pull_request.id));
+    let review_ids: BTreeSet<String> = pull_requests
+        .clone()
+        .flat_map(|pull_request| pull_request.published_reviews())
+        .map(|review| review.id.clone())
+        .collect();
+    let commit_ids: BTreeSet<String> = pull_requests
+        .flat_map(|pull_request| pull_request.pushed_commits())
+        .map(|commit| commit.id.clone())
+        .collect();
+
+    assert_eq!(
+        stored_ids(
+            db_pool,
+            "SELECT pull_request_id FROM user_contributions WHERE user_id = ANY($1)",
+            vec![user_id.to_string()],
+        )
+        .await,
+        pull_request_ids
+    );
+    assert_eq!(
+        stored_ids(
+            db_pool,
+            "SELECT id FROM reviews WHERE pull_request_id = ANY($1)",
+            pull_request_ids.iter().cloned().collect(),
+        )
+        .await,
+        review_ids
+    );
+    assert_eq!(
+        stored_ids(
+            db_pool,
+            "SELECT id FROM commits WHERE pull_request_id = ANY($1)",
+            pull_request_ids.iter().cloned().collect(),
+        )
+        .await,
+        commit_ids
+    );
+
+    let days: Vec<(NaiveDate, i32, i16)> = sqlx::query(
+        r#"
+        SELECT date, contribution_count, level FROM contribution_days
+        WHERE user_id = $1
+        ORDER BY date
+        "#,
+    )
+    .bind(user_id)
+    .map(|row: PgRow| {
+        (
+            row.get("date"),
+            row.get("contribution_count"),
+            row.get("level"),
+        )
+    })
+    .fetch_all(db_pool)
+    .await
+    .unwrap();
+    assert_eq!(
+        days,
+        user.calendar
+            .iter()
+            .map(|day| (day.date, day.count as i32, calendar_level(day.count)))
+            .collect::<Vec<_>>()
+    );
+}
+
+#[test]
+#[ignore = "needs a postgres database in DATABASE_URL"]
+fn syncs_a_generated_user_into_postgres() {
+    let _database = lock_database();
+    let options = DatasetOptions {
+        start: Utc::now() - Duration::days(700),
+        days: 670,
+        ..DatasetOptions::default()
+    };
+    let dataset = Dataset::generate(&options);
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let user_id = test_user_id(LOGIN);
+    let everything = SyncWindow {
+        from: Some(options.start),
+        to: Utc::now(),
+    };
+
+    task::block_on(async {
+        let db_pool = PgPool::new(&database_url()).await.unwrap();
+        ghworker::migrate(&db_pool).await.unwrap();
+
+        ghworker::run(
+            &db_pool,
+            &server.api(1),
+            &SyncOptions::from_iter(&["ghworker", "--backfill"]),
+            user_id.clone(),
+            LOGIN.into(),
+            "test-token".into(),
+        )
+        .await
+        .unwrap();
+        assert_stored(&db_pool, &dataset, &user_id, LOGIN, &everything).await;
+
+        let merged_id = merge_first_open_pull_request(&server, dataset.user(LOGIN).unwrap());
+        let report = ghworker::run(
+            &db_pool,
+            &server.api(1),
+            &SyncOptions::from_iter(&["ghworker"]),
+            user_id.clone(),
+            LOGIN.into(),
+            "test-token".into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.pull_requests.updated, 1);
+        let merged: bool = sqlx::query("SELECT merged FROM pull_requests WHERE id = $1")
+            .bind(&merged_id)
+            .map(|row: PgRow| row.get("merged"))
+            .fetch_one(&db_pool)
+            .await
+            .unwrap();
+        assert!(merged);
+
+        let report = ghworker::run(
+            &db_pool,
+            &server.api(1),
+            &SyncOptions::from_iter(&["ghworker", "--full-resync"]),
+            user_id.clone(),
+            LOGIN.into(),
+            "test-token".into(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(report.summary.pull_requests.inserted, 0);
+        assert_eq!(report.summary.pull_requests.updated, 0);
+        assert_eq!(report.summary.reviews.inserted, 0);
+        assert_eq!(report.summary.commits.inserted, 0);
+        assert_eq!(
+            report.summary.contribution_days.unchanged,
+            dataset.user(LOGIN).unwrap().calendar.len()
+        );
+
+        ghworker::unregister(&db_pool, &user_id, LOGIN)
+            .await
+            .unwrap();
+    });
+}
+
+#[test]
+#[ignore = "needs a postgres database in DATABASE_URL"]
+fn catches_up_on_syncs_older_than_a_year() {
+    let _database = lock_database();
+    let options = DatasetOptions {
+        start: Utc::now() - Duration::days(700),
+        days: 670,
+        ..DatasetOptions::default()
+    };
+    let dataset = Dataset::generate(&options);
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let login = "fake-user-2";
+    let user_id = test_user_id(login);
+
+    task::block_on(async {
+        let db_pool = PgPool::new(&database_url()).await.unwrap();
+        ghworker::migrate(&db_pool).await.unwrap();
+
+        for _ in 0..2 {
+            ghworker::run(
+                &db_pool,
+                &server.api(1),
+                &SyncOptions::from_iter(&["ghworker"]),
+                user_id.clone(),
+                login.into(),
+                "test-token".into(),
+            )
+            .await
+            .unwrap();
+
+            sqlx::query("UPDATE sync_state SET last_synced_at = $1 WHERE user_id = $2")
+                .bind(options.start)
+                .bind(&user_id)
+                .execute(&db_pool)
+                .await
+                .unwrap();
+        }
+
+        assert_stored(
+            &db_pool,
+            &dataset,
+            &user_id,
+            login,
+            &SyncWindow {
+                from: Some(options.start),
+                to: Utc::now(),
+            },
+        )
+        .await;
+
+        ghworker::unregister(&db_pool, &user_id, login)
+            .await
+            .unwrap();
+    });
+}
+
+#[test]
+#[ignore = "needs a postgres database in DATABASE_URL"]
+fn keeps_the_contributions_when_the_calendar_fails() {
+    let _database = lock_database();
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let user_id = test_user_id(LOGIN);
+    server.fail(Some("ContributionsQuery"), 10, Failure::Status(502));
+
+    task::block_on(async {
+        let db_pool = PgPool::new(&database_url()).await.unwrap();
+        ghworker::migrate(&db_pool).await.unwrap();
+
+        let report = ghworker::run(
+            &db_pool,
+            &server.api(2),
+            &SyncOptions::from_iter(&["ghworker"]),
+            user_id.clone(),
+            LOGIN.into(),
+            "test-token".into(),
+        )
+        .await
+        .unwrap();
+
+        assert!(report.summary.pull_requests.inserted > 0);
+        assert_eq!(report.summary.contribution_days.inserted, 0);
+        assert_eq!(
+            stored_ids(
+                &db_pool,
+                "SELECT contribution_type FROM sync_state WHERE user_id = ANY($1)",
+                vec![user_id.clone()],
+            )
+            .await
+            .len(),
+            2
+        );
+
+        ghworker::unregister(&db_pool, &user_id, LOGIN)
+            .await
+            .unwrap();
+    });
+}
+
+#[test]
+#[ignore = "needs a postgres database in DATABASE_URL"]
+fn relinks_a_github_account_to_another_user() {
+    let _database = lock_database();
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let login = "fake-user-1";
+    let previous_user_id = test_user_id(login);
+    let user_id = test_user_id(login);
+
+    task::block_on(async {
+        let db_pool = PgPool::new(&database_url()).await.unwrap();
+        ghworker::migrate(&db_pool).await.unwrap();
+
+        for user_id in &[&previous_user_id, &user_id] {
+            ghworker::run(
+                &db_pool,
+                &server.api(1),
+                &SyncOptions::from_iter(&["ghworker"]),
+                user_id.to_string(),
+                login.into(),
+                "test-token".into(),
+            )
+            .await
+            .unwrap();
+        }
+
+        let node_ids: Vec<(String, Option<String>)> = sqlx::query(
+            "SELECT user_id, node_id FROM github_accounts WHERE user_id = ANY($1) ORDER BY user_id",
+        )
+        .bind(vec![previous_user_id.clone(), user_id.clone()])
+        .map(|row: PgRow| (row.get("user_id"), row.get("node_id")))
+        .fetch_all(&db_pool)
+        .await
+        .unwrap();
+        assert_eq!(
+            node_ids,
+            vec![
+                (previous_user_id.clone(), None),
+                (
+                    user_id.clone(),
+                    Some(dataset.user(login).unwrap().id.clone())
+                ),
+            ]
+        );
+        assert_eq!(
+            stored_ids(
+                &db_pool,
+                "SELECT contribution_type FROM sync_state WHERE user_id = ANY($1)",
+                vec![user_id.clone()],
+            )
+            .await
+            .len(),
+            2
+        );
+        assert_eq!(
+            ghworker::resolve_user_id(&db_pool, login).await.unwrap(),
+            user_id
+        );
+
+        ghworker::unregister(&db_pool, &previous_user_id, login)
+            .await
+            .unwrap();
+        assert_stored(&db_pool, &dataset, &user_id, login, &all_time()).await;
+
+        ghworker::unregister(&db_pool, &user_id, login)
+            .await
+            .unwrap();
+        assert!(stored_ids(
+            &db_pool,
+            "SELECT user_id FROM github_accounts WHERE user_id = ANY($1)",
+            vec![previous_user_id.clone(), user_id.clone()],
+        )
+        .await
+        .is_empty());
+        assert!(stored_ids(
+            &db_pool,
+            "SELECT pull_request_id FROM user_contributions WHERE user_id = ANY($1)",
+            vec![previous_user_id.clone(), user_id.clone()],
+        )
+        .await
+        .is_empty());
+    });
+}
+
+#[test]
+#[ignore = "needs kafka in QUEUE_URL and a postgres database in DATABASE_URL"]
+fn syncs_users_requested_on_the_queue() {
+    let _database = lock_database();
+    use kafka::producer::{Producer, Record};
+
+    let dataset = Dataset::generate(&DatasetOptions::default());
+    let server = MockGithub::start(dataset.clone(), small_pages());
+    let queue_url = std::env::var("QUEUE_URL").expect("QUEUE_URL must point at a kafka broker");
+    let suffix = Utc::now().timestamp_nanos_opt().unwrap();
+    let topic = format!("ghworker-e2e-{}", suffix);
+    let logins = ["fake-user-1", "fake-user-2"];
+    let user_ids: Vec<String> = logins.iter().map(|login| test_user_id(login)).collect();
+
+    let mut producer = Producer::from_hosts(vec![queue_url.clone()])
+        .create()
+        .unwrap();
+    for (user_id, login) in user_ids.iter().zip(logins.iter()) {
+        let payload = serde_json::json!({
+            "user_id": user_id,
+            "username": login,
+            "token": "test-token",
+        })
+        .to_string();
+        let mut attempts = 0;
+
+        while let Err(e) = producer.send(&Record::from_key_value(
+            &topic,
+            "SYNC:GITHUB",
+            payload.as_str(),
+        )) {
+            attempts += 1;
+            assert!(attempts < 20, "could not publish the sync event: {}", e);
+            std::thread::sleep(std::time::Duration::from_millis(500));
+        }
+    }
+
+    let queue_options = QueueOptions::from_iter(&[
+        "ghworker",
+        "--queue-url",
+        &queue_url,
+        "--queue-topic",
+        &topic,
+        "--queue-group",
+        &format!("{}-group", topic),
+        "--queue-output-topic",
+        &format!("{}-events", topic),
+        "--queue-dead-letter-topic",
+        &format!("{}-dead-letters", topic),
+        "--queue-reconnect-delay",
+        "1",
+    ]);
+
+    task::block_on(async {
+        let db_pool = PgPool::new(&database_url()).await.unwrap();
+        ghworker::migrate(&db_pool).await.unwrap();
+
+        let github_options = server.options(1);
+        let sync_options = SyncOptions::from_iter(&["ghworker"]);
+        let consume = ghworker::consume(&db_pool, &queue_options, &github_options, &sync_options);
+        let synced = async {
+            loop {
+                let stored = stored_ids(
+                    &db_pool,
+                    "SELECT DISTINCT user_id FROM user_contributions WHERE user_id = ANY($1)",
+                    user_ids.clone(),
+                )
+                .await;
+
+                if stored.len() == user_ids.len() {
+                    break;
+                }
+                task::sleep(std::time::Duration::from_millis(250)).await;
+            }
+        };
+
+        async_std::future::timeout(
+            std::time::Duration::from_secs(60),
+            futures::future::select(Box::pin(consume), Box::pin(synced)),
+        )
+        .await
+        .expect("the queued users were not synced in time");
+
+        for (user_id, login) in user_ids.iter().zip(logins.iter()) {
+            assert_stored(&db_pool, &dataset, user_id, login, &all_time()).await;
+            ghworker::unregister(&db_pool, user_id, login)
+                .await
+                .unwrap();
+        }
+    });
+}
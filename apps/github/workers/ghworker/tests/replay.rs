@@ -0,0 +1,304 @@
+use async_std::task;
+use chrono::{TimeZone, Utc};
+use ghworker::dto::{AuthorKind, ContributionType, PullRequestsDTO, SyncWindow};
+use ghworker::{
+    get_pull_request_contributions, get_pull_request_review_contributions, GithubClient,
+    GithubError, GithubOptions,
+};
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+fn fixture(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(format!("{}.json", name))
+}
+
+fn replay_client(name: &str, max_attempts: u32) -> GithubClient {
+    let path = fixture(name);
+    let max_attempts = max_attempts.to_string();
+    let options = GithubOptions::from_iter(&[
+        "ghworker",
+        "--github-replay",
+        path.to_str().unwrap(),
+        "--github-max-attempts",
+        &max_attempts,
+        "--github-retry-base-delay-ms",
+        "1",
+        "--github-retry-max-delay-ms",
+        "1",
+    ]);
+
+    GithubClient::new(&options, "test-token".into()).unwrap()
+}
+
+fn window() -> SyncWindow {
+    SyncWindow {
+        from: None,
+        to: Utc.with_ymd_and_hms(2020, 4, 1, 0, 0, 0).unwrap(),
+    }
+}
+
+#[test]
+fn pull_request_contributions_follow_every_cursor() {
+    let client = replay_client("pull_request_pagination", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    let user = dto.user.as_ref().unwrap();
+    assert_eq!(user.login.as_deref(), Some("octocat"));
+    assert_eq!(dto.pull_requests.len(), 2);
+    assert!(dto
+        .contributions
+        .contains(&("PR_1".to_string(), ContributionType::PullRequests)));
+    assert!(dto
+        .contributions
+        .contains(&("PR_2".to_string(), ContributionType::PullRequests)));
+
+    let first = &dto.pull_requests["PR_1"];
+    assert_eq!(first.repository, "octo-org/app");
+    assert!(!first.merged);
+    assert_eq!(
+        first
+            .commits
+            .iter()
+            .map(|c| c.hash.as_str())
+            .collect::<Vec<_>>(),
+        vec!["aaa1111"]
+    );
+
+    let second = &dto.pull_requests["PR_2"];
+    assert!(second.merged);
+    assert!(second.date_merged.is_some());
+    assert!(second.reviews.is_empty());
+}
+
+#[test]
+fn reviews_are_refetched_across_pages_when_truncated() {
+    let client = replay_client("pull_request_pagination", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    let reviews = &dto.pull_requests["PR_1"].reviews;
+    assert_eq!(
+        reviews.iter().map(|r| r.id.as_str()).collect::<Vec<_>>(),
+        vec!["PRR_1", "PRR_2"]
+    );
+    assert_eq!(reviews[0].author.kind, AuthorKind::User);
+    assert_eq!(reviews[0].author.login.as_deref(), Some("hubot"));
+    assert_eq!(reviews[1].author.kind, AuthorKind::Ghost);
+    assert_eq!(reviews[1].author.login, None);
+}
+
+#[test]
+fn commits_are_refetched_across_pages_when_truncated() {
+    let client = replay_client("pull_request_pagination", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    let commits = &dto.pull_requests["PR_2"].commits;
+    assert_eq!(commits.len(), 1);
+    assert_eq!(commits[0].hash, "ccc3333");
+    assert_eq!(commits[0].author.kind, AuthorKind::Unlinked);
+    assert_eq!(commits[0].author.id, None);
+    assert_eq!(commits[0].author.name.as_deref(), Some("Mona Lisa"));
+    assert_eq!(commits[0].author.email.as_deref(), Some("mona@example.com"));
+}
+
+#[test]
+fn review_contributions_keep_every_author_kind() {
+    let client = replay_client("review_contribution_authors", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_review_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    assert_eq!(dto.pull_requests.len(), 2);
+    assert!(dto
+        .contributions
+        .iter()
+        .all(|(_, contribution_type)| *contribution_type == ContributionType::PullRequestReviews));
+
+    let bot_pull_request = &dto.pull_requests["PR_3"];
+    assert_eq!(bot_pull_request.author.kind, AuthorKind::Bot);
+    assert_eq!(
+        bot_pull_request.author.id.as_deref(),
+        Some("BOT_dependabot")
+    );
+    assert_eq!(bot_pull_request.reviews.len(), 1);
+    assert!(bot_pull_request.reviews[0].viewer_did_author);
+    assert!(bot_pull_request.commits.is_empty());
+
+    let ghost_pull_request = &dto.pull_requests["PR_4"];
+    assert_eq!(ghost_pull_request.author.kind, AuthorKind::Ghost);
+    let mannequin = ghost_pull_request
+        .reviews
+        .iter()
+        .find(|review| review.id == "PRR_5")
+        .unwrap();
+    assert_eq!(mannequin.author.kind, AuthorKind::Mannequin);
+    assert_eq!(mannequin.author.login.as_deref(), Some("old-account"));
+}
+
+#[test]
+fn null_contribution_nodes_leave_an_empty_dto() {
+    let client = replay_client("empty_review_contributions", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_review_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    assert!(dto.user.is_some());
+    assert!(dto.pull_requests.is_empty());
+    assert!(dto.contributions.is_empty());
+}
+
+#[test]
+fn partial_errors_keep_the_rest_of_the_response() {
+    let client = replay_client("saml_protected_contribution", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap();
+
+    assert_eq!(dto.pull_requests.len(), 1);
+    assert!(dto.pull_requests.contains_key("PR_1"));
+}
+
+#[test]
+fn errors_that_null_the_user_are_not_partial() {
+    let client = replay_client("contributions_span_too_long", 3);
+    let mut dto = PullRequestsDTO::new();
+
+    let err = task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap_err();
+
+    assert!(matches!(
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<GithubError>()),
+        Some(GithubError::GraphQL(_))
+    ));
+}
+
+#[test]
+fn invalid_queries_are_not_retried() {
+    let client = replay_client("invalid_query", 3);
+    let mut dto = PullRequestsDTO::new();
+
+    let err = task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap_err();
+
+    assert!(matches!(
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<GithubError>()),
+        Some(GithubError::GraphQL(_))
+    ));
+}
+
+fn not_found(err: &anyhow::Error) -> bool {
+    match err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<GithubError>())
+    {
+        Some(GithubError::GraphQL(errors)) => errors
+            .iter()
+            .any(|error| error.error_type.as_deref() == Some("NOT_FOUND")),
+        _ => false,
+    }
+}
+
+#[test]
+fn missing_users_are_not_found() {
+    let client = replay_client("missing_user", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    let err = task::block_on(get_pull_request_contributions(
+        &client,
+        "nobody".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap_err();
+
+    assert!(not_found(&err), "unexpected error {:#}", err);
+    assert!(dto.user.is_none());
+    assert!(dto.pull_requests.is_empty());
+}
+
+#[test]
+fn replayed_server_errors_are_retried() {
+    let client = replay_client("bad_gateway_then_missing_user", 2);
+    let mut dto = PullRequestsDTO::new();
+
+    let err = task::block_on(get_pull_request_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ))
+    .unwrap_err();
+
+    assert!(not_found(&err), "unexpected error {:#}", err);
+}
+
+#[test]
+fn replay_fails_without_a_recorded_response() {
+    let client = replay_client("bad_gateway_then_missing_user", 1);
+    let mut dto = PullRequestsDTO::new();
+
+    let result = task::block_on(get_pull_request_review_contributions(
+        &client,
+        "octocat".into(),
+        &window(),
+        &mut dto,
+    ));
+
+    assert!(result.is_err());
+}
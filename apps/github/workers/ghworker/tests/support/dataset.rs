@@ -0,0 +1,400 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::cmp::Reverse;
+
+#[derive(Debug, Clone)]
+pub struct DatasetOptions {
+    pub seed: u64,
+    pub users: usize,
+    pub repositories: usize,
+    pub authored_per_user: usize,
+    pub reviewed_per_user: usize,
+    pub max_reviews: usize,
+    pub max_commits: usize,
+    pub start: DateTime<Utc>,
+    pub days: i64,
+}
+
+impl Default for DatasetOptions {
+    fn default() -> DatasetOptions {
+        DatasetOptions {
+            seed: 42,
+            users: 3,
+            repositories: 4,
+            authored_per_user: 12,
+            reviewed_per_user: 8,
+            max_reviews: 6,
+            max_commits: 7,
+            start: Utc::now() - Duration::days(360),
+            days: 330,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FakeActor {
+    User { id: String, login: String },
+    Bot { id: String, login: String },
+    Mannequin { id: String, login: String },
+    Ghost,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeGitActor {
+    pub name: String,
+    pub email: String,
+    pub user: Option<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeReview {
+    pub id: String,
+    pub author: FakeActor,
+    pub published_at: Option<DateTime<Utc>>,
+    pub viewer_did_author: bool,
+    pub state: &'static str,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeCommit {
+    pub id: String,
+    pub oid: String,
+    pub message_headline: String,
+    pub pushed_at: Option<DateTime<Utc>>,
+    pub author: Option<FakeGitActor>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakePullRequest {
+    pub id: String,
+    pub repository: String,
+    pub number: i64,
+    pub title: String,
+    pub author: FakeActor,
+    pub created_at: DateTime<Utc>,
+    pub closed_at: Option<DateTime<Utc>>,
+    pub merged_at: Option<DateTime<Utc>>,
+    pub additions: i64,
+    pub deletions: i64,
+    pub changed_files: i64,
+    pub base_ref_name: String,
+    pub head_ref_name: String,
+    pub reviews: Vec<FakeReview>,
+    pub commits: Vec<FakeCommit>,
+}
+
+impl FakePullRequest {
+    pub fn published_reviews(&self) -> impl Iterator<Item = &FakeReview> {
+        self.reviews
+            .iter()
+            .filter(|review| review.published_at.is_some())
+    }
+
+    pub fn pushed_commits(&self) -> impl Iterator<Item = &FakeCommit> {
+        self.commits
+            .iter()
+            .filter(|commit| commit.pushed_at.is_some())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FakeContribution {
+    pub occurred_at: DateTime<Utc>,
+    pub pull_request: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct FakeUser {
+    pub id: String,
+    pub login: String,
+    pub pull_request_contributions: Vec<FakeContribution>,
+    pub review_contributions: Vec<FakeContribution>,
+    pub calendar: Vec<FakeCalendarDay>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct FakeCalendarDay {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Dataset {
+    pub users: Vec<FakeUser>,
+    pub pull_requests: Vec<FakePullRequest>,
+}
+
+impl Dataset {
+    pub fn generate(options: &DatasetOptions) -> Dataset {
+        let mut generator = Generator {
+            rng: StdRng::seed_from_u64(options.seed),
+            options: options.clone(),
+            numbers: vec![0; options.repositories.max(1)],
+            dataset: Dataset::default(),
+        };
+
+        generator.dataset.users = (0..options.users)
+            .map(|index| FakeUser {
+                id: format!("U_{}", index),
+                login: format!("fake-user-{}", index),
+                pull_request_contributions: Vec::new(),
+                review_contributions: Vec::new(),
+                calendar: Vec::new(),
+            })
+            .collect();
+
+        for user in 0..options.users {
+            for _ in 0..options.authored_per_user {
+                let author = generator.user_actor(user);
+                let pull_request = generator.pull_request(author, None);
+                let occurred_at = generator.dataset.pull_requests[pull_request].created_at;
+
+                generator.dataset.users[user]
+                    .pull_request_contributions
+                    .push(FakeContribution {
+                        occurred_at,
+                        pull_request,
+                    });
+            }
+
+            for _ in 0..options.reviewed_per_user {
+                let author = generator.other_actor(user);
+                let pull_request = generator.pull_request(author, Some(user));
+                let occurred_at = generator.dataset.pull_requests[pull_request]
+                    .reviews
+                    .iter()
+                    .find(|review| review.viewer_did_author)
+                    .and_then(|review| review.published_at)
+                    .unwrap();
+
+                generator.dataset.users[user]
+                    .review_contributions
+                    .push(FakeContribution {
+                        occurred_at,
+                        pull_request,
+                    });
+            }
+        }
+
+        for user in 0..options.users {
+            generator.dataset.users[user].calendar = generator.calendar();
+        }
+
+        for user in &mut generator.dataset.users {
+            user.pull_request_contributions
+                .sort_by_key(|contribution| Reverse(contribution.occurred_at));
+            user.review_contributions
+                .sort_by_key(|contribution| Reverse(contribution.occurred_at));
+        }
+
+        generator.dataset
+    }
+
+    pub fn user(&self, login: &str) -> Option<&FakeUser> {
+        self.users
+            .iter()
+            .find(|user| user.login.eq_ignore_ascii_case(login))
+    }
+
+    pub fn pull_request(&self, repository: &str, number: i64) -> Option<&FakePullRequest> {
+        self.pull_requests.iter().find(|pull_request| {
+            pull_request.repository.eq_ignore_ascii_case(repository)
+                && pull_request.number == number
+        })
+    }
+}
+
+struct Generator {
+    rng: StdRng,
+    options: DatasetOptions,
+    numbers: Vec<i64>,
+    dataset: Dataset,
+}
+
+impl Generator {
+    fn user_actor(&self, user: usize) -> FakeActor {
+        let user = &self.dataset.users[user];
+
+        FakeActor::User {
+            id: user.id.clone(),
+            login: user.login.clone(),
+        }
+    }
+
+    fn other_actor(&mut self, except: usize) -> FakeActor {
+        match self.rng.gen_range(0, 10) {
+            0 => FakeActor::Ghost,
+            1 => FakeActor::Bot {
+                id: "BOT_dependabot".into(),
+                login: "dependabot".into(),
+            },
+            2 => FakeActor::Mannequin {
+                id: "MQ_imported".into(),
+                login: "imported-account".into(),
+            },
+            _ => {
+                let others: Vec<usize> = (0..self.dataset.users.len())
+                    .filter(|user| *user != except)
+                    .collect();
+
+                if others.is_empty() {
+                    FakeActor::Ghost
+                } else {
+                    let other = others[self.rng.gen_range(0, others.len())];
+                    self.user_actor(other)
+                }
+            }
+        }
+    }
+
+    fn pull_request(&mut self, author: FakeActor, reviewer: Option<usize>) -> usize {
+        let index = self.dataset.pull_requests.len();
+        let repository = self.rng.gen_range(0, self.numbers.len());
+        self.numbers[repository] += 1;
+
+        let created_at = self.options.start
+            + Duration::minutes(self.rng.gen_range(0, self.options.days.max(1) * 24 * 60));
+        let (closed_at, merged_at) = match self.rng.gen_range(0, 3) {
+            0 => (None, None),
+            1 => {
+                let closed_at = created_at + Duration::hours(self.rng.gen_range(1, 200));
+                (Some(closed_at), None)
+            }
+            _ => {
+                let merged_at = created_at + Duration::hours(self.rng.gen_range(1, 200));
+                (Some(merged_at), Some(merged_at))
+            }
+        };
+
+        let mut reviews: Vec<FakeReview> = (0..self.rng.gen_range(0, self.options.max_reviews + 1))
+            .map(|review| {
+                let author = match reviewer {
+                    Some(reviewer) => self.other_actor(reviewer),
+                    None => self.other_actor(usize::MAX),
+                };
+                self.review(index, review, created_at, author, false)
+            })
+            .collect();
+
+        if let Some(reviewer) = reviewer {
+            let author = self.user_actor(reviewer);
+            let position = self.rng.gen_range(0, reviews.len() + 1);
+            let mut review = self.review(index, reviews.len(), created_at, author, true);
+            if review.published_at.is_none() {
+                review.published_at = Some(created_at + Duration::hours(1));
+                review.state = "COMMENTED";
+            }
+            reviews.insert(position, review);
+        }
+
+        let git_author = match &author {
+            FakeActor::User { id, login } => Some(FakeGitActor {
+                name: login.clone(),
+                email: format!("{}@users.noreply.github.com", login),
+                user: Some((id.clone(), login.clone())),
+            }),
+            _ => None,
+        };
+        let commits = (0..self.rng.gen_range(1, self.options.max_commits.max(1) + 1))
+            .map(|commit| self.commit(index, commit, created_at, git_author.clone()))
+            .collect();
+
+        self.dataset.pull_requests.push(FakePullRequest {
+            id: format!("PR_{}", index),
+            repository: format!("fake-org/repo-{}", repository),
+            number: self.numbers[repository],
+            title: format!("Fake pull request {}", index),
+            author,
+            created_at,
+            closed_at,
+            merged_at,
+            additions: self.rng.gen_range(0, 500),
+            deletions: self.rng.gen_range(0, 500),
+            changed_files: self.rng.gen_range(1, 30),
+            base_ref_name: "main".into(),
+            head_ref_name: format!("feature/{}", index),
+            reviews,
+            commits,
+        });
+
+        index
+    }
+
+    fn calendar(&mut self) -> Vec<FakeCalendarDay> {
+        let today = Utc::now().date_naive();
+        let first_day = today - Duration::days(364);
+        let first_day =
+            first_day - Duration::days(first_day.weekday().num_days_from_sunday() as i64);
+
+        first_day
+            .iter_days()
+            .take_while(|date| *date <= today)
+            .map(|date| FakeCalendarDay {
+                date,
+                count: match self.rng.gen_range(0, 10) {
+                    0..=3 => 0,
+                    _ => self.rng.gen_range(1, 20),
+                },
+            })
+            .collect()
+    }
+
+    fn review(
+        &mut self,
+        pull_request: usize,
+        review: usize,
+        created_at: DateTime<Utc>,
+        author: FakeActor,
+        viewer_did_author: bool,
+    ) -> FakeReview {
+        let (state, published_at) = match self.rng.gen_range(0, 10) {
+            0 => ("PENDING", None),
+            1 | 2 => ("CHANGES_REQUESTED", Some(())),
+            3 => ("DISMISSED", Some(())),
+            4..=6 => ("COMMENTED", Some(())),
+            _ => ("APPROVED", Some(())),
+        };
+
+        FakeReview {
+            id: format!("PRR_{}_{}", pull_request, review),
+            author,
+            published_at: published_at
+                .map(|_| created_at + Duration::minutes(self.rng.gen_range(1, 10_000))),
+            viewer_did_author,
+            state,
+            body: format!("Review {} of pull request {}", review, pull_request),
+        }
+    }
+
+    fn commit(
+        &mut self,
+        pull_request: usize,
+        commit: usize,
+        created_at: DateTime<Utc>,
+        author: Option<FakeGitActor>,
+    ) -> FakeCommit {
+        let author = match self.rng.gen_range(0, 10) {
+            0 => None,
+            1 | 2 => Some(FakeGitActor {
+                name: format!("Unlinked Author {}", commit),
+                email: format!("unlinked-{}@example.com", commit),
+                user: None,
+            }),
+            _ => author,
+        };
+
+        FakeCommit {
+            id: format!("C_{}_{}", pull_request, commit),
+            oid: format!("{:07x}", pull_request * 1000 + commit),
+            message_headline: format!("Commit {} of pull request {}", commit, pull_request),
+            pushed_at: if self.rng.gen_range(0, 10) == 0 {
+                None
+            } else {
+                Some(created_at - Duration::minutes(self.rng.gen_range(1, 10_000)))
+            },
+            author,
+        }
+    }
+}
@@ -0,0 +1,7 @@
+#![allow(dead_code)]
+
+pub mod dataset;
+pub mod server;
+
+pub use dataset::{Dataset, DatasetOptions};
+pub use server::{Failure, MockGithub, PageSizes};
@@ -0,0 +1,694 @@
+use super::dataset::{
+    Dataset, FakeActor, FakeCalendarDay, FakeCommit, FakeContribution, FakeGitActor,
+    FakePullRequest, FakeReview,
+};
+use chrono::{DateTime, Datelike, Duration, Utc};
+use ghworker::{GithubApi, GithubOptions};
+use serde_json::{json, Value};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use structopt::StructOpt;
+use tiny_http::{Header, Request, Response, Server};
+
+const RATE_LIMIT: i64 = 5000;
+const EMPTY_COLOR: &str = "#ebedf0";
+const CALENDAR_COLORS: [&str; 4] = ["#9be9a8", "#40c463", "#30a14e", "#216e39"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct PageSizes {
+    pub contributions: usize,
+    pub reviews: usize,
+    pub commits: usize,
+    pub nested: usize,
+}
+
+impl Default for PageSizes {
+    fn default() -> PageSizes {
+        PageSizes {
+            contributions: 100,
+            reviews: 100,
+            commits: 100,
+            nested: 100,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum Failure {
+    Status(u16),
+    SecondaryRateLimit(u64),
+    RateLimited,
+    GraphQL(String),
+    Malformed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReceivedQuery {
+    pub operation: String,
+    pub variables: Value,
+}
+
+struct Injection {
+    operation: Option<String>,
+    remaining: usize,
+    failure: Failure,
+}
+
+struct State {
+    dataset: Dataset,
+    page_sizes: PageSizes,
+    injections: Vec<Injection>,
+    received: Vec<ReceivedQuery>,
+    remaining: i64,
+    reset_at: DateTime<Utc>,
+}
+
+pub struct MockGithub {
+    server: Arc<Server>,
+    state: Arc<Mutex<State>>,
+    port: u16,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockGithub {
+    pub fn start(dataset: Dataset, page_sizes: PageSizes) -> MockGithub {
+        let server = Arc::new(Server::http("127.0.0.1:0").unwrap());
+        let port = server.server_addr().to_ip().unwrap().port();
+        let state = Arc::new(Mutex::new(State {
+            dataset,
+            page_sizes,
+            injections: Vec::new(),
+            received: Vec::new(),
+            remaining: RATE_LIMIT,
+            reset_at: Utc::now() + Duration::hours(1),
+        }));
+
+        let handle = {
+            let server = server.clone();
+            let state = state.clone();
+
+            thread::spawn(move || {
+                for request in server.incoming_requests() {
+                    respond(&state, request);
+                }
+            })
+        };
+
+        MockGithub {
+            server,
+            state,
+            port,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn endpoint(&self) -> String {
+        format!("http://127.0.0.1:{}/graphql", self.port)
+    }
+
+    pub fn options(&self, max_attempts: u32) -> GithubOptions {
+        GithubOptions::from_iter(&[
+            "ghworker",
+            "--github-endpoint",
+            &self.endpoint(),
+            "--github-timeout",
+            "10",
+            "--github-max-attempts",
+            &max_attempts.to_string(),
+            "--github-retry-base-delay-ms",
+            "10",
+            "--github-retry-max-delay-ms",
+            "50",
+        ])
+    }
+
+    pub fn api(&self, max_attempts: u32) -> GithubApi {
+        GithubApi::new(&self.options(max_attempts)).unwrap()
+    }
+
+    pub fn fail(&self, operation: Option<&str>, times: usize, failure: Failure) {
+        self.state.lock().unwrap().injections.push(Injection {
+            operation: operation.map(String::from),
+            remaining: times,
+            failure,
+        });
+    }
+
+    pub fn update(&self, change: impl FnOnce(&mut Dataset)) {
+        change(&mut self.state.lock().unwrap().dataset);
+    }
+
+    pub fn received(&self) -> Vec<ReceivedQuery> {
+        self.state.lock().unwrap().received.clone()
+    }
+
+    pub fn received_count(&self, operation: &str) -> usize {
+        self.state
+            .lock()
+            .unwrap()
+            .received
+            .iter()
+            .filter(|query| query.operation == operation)
+            .count()
+    }
+}
+
+impl Drop for MockGithub {
+    fn drop(&mut self) {
+        self.server.unblock();
+
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+fn respond(state: &Mutex<State>, mut request: Request) {
+    let authorized = request.headers().iter().any(|header| {
+        header.field.equiv("Authorization") && header.value.as_str().starts_with("Bearer ")
+    });
+
+    let mut body = String::new();
+    let response = if !authorized {
+        json_response(401, json!({ "message": "Bad credentials" }).to_string())
+    } else if request.as_reader().read_to_string(&mut body).is_err() {
+        json_response(
+            400,
+            json!({ "message": "Problems parsing JSON" }).to_string(),
+        )
+    } else {
+        let query: Value = serde_json::from_str(&body).unwrap_or(Value::Null);
+        let operation = query["operationName"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string();
+        let variables = query["variables"].clone();
+
+        let mut state = state.lock().unwrap();
+        state.received.push(ReceivedQuery {
+            operation: operation.clone(),
+            variables: variables.clone(),
+        });
+
+        match state.take_failure(&operation) {
+            Some(failure) => failure_response(&failure),
+            None => state.answer(&operation, &variables),
+        }
+    };
+
+    let _ = request.respond(response);
+}
+
+fn json_response(status: u16, body: String) -> Response<std::io::Cursor<Vec<u8>>> {
+    Response::from_string(body)
+        .with_status_code(status)
+        .with_header(header("Content-Type", "application/json"))
+}
+
+fn header(name: &str, value: &str) -> Header {
+    Header::from_bytes(name.as_bytes(), value.as_bytes()).unwrap()
+}
+
+fn failure_response(failure: &Failure) -> Response<std::io::Cursor<Vec<u8>>> {
+    match failure {
+        Failure::Status(status) => json_response(
+            *status,
+            json!({ "message": format!("Injected {} failure", status) }).to_string(),
+        ),
+        Failure::SecondaryRateLimit(retry_after) => json_response(
+            403,
+            json!({ "message": "You have exceeded a secondary rate limit" }).to_string(),
+        )
+        .with_header(header("Retry-After", &retry_after.to_string())),
+        Failure::RateLimited => {
+            let reset_at = Utc::now() + Duration::seconds(1);
+
+            json_response(
+                200,
+                json!({
+                    "data": null,
+                    "errors": [{ "type": "RATE_LIMITED", "message": "API rate limit exceeded" }],
+                })
+                .to_string(),
+            )
+            .with_header(header("X-RateLimit-Remaining", "0"))
+            .with_header(header("X-RateLimit-Reset", &reset_at.timestamp().to_string()))
+        }
+        Failure::GraphQL(error_type) => json_response(
+            200,
+            json!({
+                "data": null,
+                "errors": [{ "type": error_type, "message": format!("Injected {} error", error_type) }],
+            })
+            .to_string(),
+        ),
+        Failure::Malformed => json_response(200, "{\"data\": {\"rateLimit\"".into()),
+    }
+}
+
+impl State {
+    fn take_failure(&mut self, operation: &str) -> Option<Failure> {
+        let injection = self.injections.iter_mut().find(|injection| {
+            injection.remaining > 0
+                && injection
+                    .operation
+                    .as_deref()
+                    .is_none_or(|name| name == operation)
+        })?;
+        injection.remaining -= 1;
+
+        Some(injection.failure.clone())
+    }
+
+    fn answer(&mut self, operation: &str, variables: &Value) -> Response<std::io::Cursor<Vec<u8>>> {
+        self.remaining = (self.remaining - 1).max(0);
+        let rate_limit = json!({
+            "cost": 1,
+            "remaining": self.remaining,
+            "resetAt": self.reset_at.to_rfc3339(),
+        });
+
+        let (data, errors) = match operation {
+            "PullRequestContributionsQuery" => {
+                self.contributions(variables, "pullRequestContributions", |user| {
+                    &user.pull_request_contributions
+                })
+            }
+            "PullRequestReviewContributionsQuery" => {
+                self.contributions(variables, "pullRequestReviewContributions", |user| {
+                    &user.review_contributions
+                })
+            }
+            "PullRequestsByIdQuery" => self.pull_requests_by_id(variables),
+            "PullRequestReviewsQuery" => self.pull_request_connection(variables, "reviews"),
+            "PullRequestCommitsQuery" => self.pull_request_connection(variables, "commits"),
+            "ContributionYearsQuery" => self.contribution_years(variables),
+            "ContributionsQuery" => self.contribution_calendar(variables),
+            _ => (
+                json!({}),
+                Some(json!([{ "message": format!("Unknown operation {}", operation) }])),
+            ),
+        };
+
+        let mut data = data;
+        data["rateLimit"] = rate_limit;
+        let mut body = json!({ "data": data });
+        if let Some(errors) = errors {
+            body["errors"] = errors;
+        }
+
+        json_response(200, body.to_string())
+            .with_header(header("X-RateLimit-Remaining", &self.remaining.to_string()))
+            .with_header(header(
+                "X-RateLimit-Reset",
+                &self.reset_at.timestamp().to_string(),
+            ))
+    }
+
+    fn contributions(
+        &self,
+        variables: &Value,
+        field: &str,
+        contributions: fn(&super::dataset::FakeUser) -> &Vec<FakeContribution>,
+    ) -> (Value, Option<Value>) {
+        let username = variables["username"].as_str().unwrap_or_default();
+        let user = match self.dataset.user(username) {
+            Some(user) => user,
+            None => {
+                return not_found(
+                    "user",
+                    format!(
+                        "Could not resolve to a User with the login of '{}'.",
+                        username
+                    ),
+                )
+            }
+        };
+
+        let (from, to) = match contributions_window(variables) {
+            Some(window) => window,
+            None => {
+                return (
+                    json!({ "user": null }),
+                    Some(json!([{
+                        "path": ["user", "contributionsCollection"],
+                        "locations": [{ "line": 3, "column": 5 }],
+                        "message": "The total time spanned by 'from' and 'to' must not exceed 1 year",
+                    }])),
+                )
+            }
+        };
+        let in_window: Vec<&FakeContribution> = contributions(user)
+            .iter()
+            .filter(|contribution| {
+                contribution.occurred_at >= from && contribution.occurred_at <= to
+            })
+            .collect();
+        let (nodes, page_info) = paginate(
+            &in_window,
+            &variables["currentCursor"],
+            self.page_sizes.contributions,
+        );
+
+        let nodes: Vec<Value> = nodes
+            .iter()
+            .map(|contribution| {
+                json!({
+                    "occurredAt": contribution.occurred_at.to_rfc3339(),
+                    "pullRequest": self.pull_request_json(
+                        &self.dataset.pull_requests[contribution.pull_request],
+                    ),
+                })
+            })
+            .collect();
+
+        let mut collection = json!({});
+        collection[field] = json!({ "pageInfo": page_info, "nodes": nodes });
+
+        (
+            json!({
+                "user": {
+                    "id": user.id,
+                    "login": user.login,
+                    "contributionsCollection": collection,
+                }
+            }),
+            None,
+        )
+    }
+
+    fn pull_requests_by_id(&self, variables: &Value) -> (Value, Option<Value>) {
+        let ids = variables["ids"].as_array().cloned().unwrap_or_default();
+        let mut errors = Vec::new();
+
+        let nodes: Vec<Value> = ids
+            .iter()
+            .enumerate()
+            .map(|(index, id)| {
+                let id = id.as_str().unwrap_or_default();
+
+                match self
+                    .dataset
+                    .pull_requests
+                    .iter()
+                    .find(|pull_request| pull_request.id == id)
+                {
+                    Some(pull_request) => {
+                        let mut node = self.pull_request_json(pull_request);
+                        node["__typename"] = json!("PullRequest");
+                        node
+                    }
+                    None => {
+                        errors.push(json!({
+                            "type": "NOT_FOUND",
+                            "path": ["nodes", index],
+                            "message": format!("Could not resolve to a node with the global id of '{}'", id),
+                        }));
+                        Value::Null
+                    }
+                }
+            })
+            .collect();
+
+        (
+            json!({ "nodes": nodes }),
+            Some(Value::Array(errors)).filter(|errors| errors != &json!([])),
+        )
+    }
+
+    fn pull_request_connection(&self, variables: &Value, field: &str) -> (Value, Option<Value>) {
+        let repository = format!(
+            "{}/{}",
+            variables["owner"].as_str().unwrap_or_default(),
+            variables["name"].as_str().unwrap_or_default()
+        );
+        let number = variables["number"].as_i64().unwrap_or_default();
+        let pull_request = match self.dataset.pull_request(&repository, number) {
+            Some(pull_request) => pull_request,
+            None => {
+                return not_found(
+                    "repository",
+                    format!(
+                        "Could not resolve to a PullRequest {}#{}.",
+                        repository, number
+                    ),
+                )
+            }
+        };
+
+        let connection = if field == "reviews" {
+            let reviews: Vec<&FakeReview> = pull_request.reviews.iter().collect();
+            let (nodes, page_info) = paginate(
+                &reviews,
+                &variables["currentCursor"],
+                self.page_sizes.reviews,
+            );
+
+            json!({ "pageInfo": page_info, "nodes": nodes.iter().map(|review| review_json(review)).collect::<Vec<_>>() })
+        } else {
+            let commits: Vec<&FakeCommit> = pull_request.commits.iter().collect();
+            let (nodes, page_info) = paginate(
+                &commits,
+                &variables["currentCursor"],
+                self.page_sizes.commits,
+            );
+
+            json!({ "pageInfo": page_info, "nodes": nodes.iter().map(|commit| commit_json(commit)).collect::<Vec<_>>() })
+        };
+
+        let mut pull_request = json!({});
+        pull_request[field] = connection;
+
+        (
+            json!({ "repository": { "pullRequest": pull_request } }),
+            None,
+        )
+    }
+
+    fn contribution_years(&self, variables: &Value) -> (Value, Option<Value>) {
+        let username = variables["username"].as_str().unwrap_or_default();
+        let user = match self.dataset.user(username) {
+            Some(user) => user,
+            None => {
+                return not_found(
+                    "user",
+                    format!(
+                        "Could not resolve to a User with the login of '{}'.",
+                        username
+                    ),
+                )
+            }
+        };
+
+        let mut years: Vec<i32> = user
+            .pull_request_contributions
+            .iter()
+            .chain(user.review_contributions.iter())
+            .map(|contribution| {
+                contribution
+                    .occurred_at
+                    .format("%Y")
+                    .to_string()
+                    .parse()
+                    .unwrap()
+            })
+            .collect();
+        years.sort_unstable_by(|a, b| b.cmp(a));
+        years.dedup();
+
+        (
+            json!({ "user": { "contributionsCollection": { "contributionYears": years } } }),
+            None,
+        )
+    }
+
+    fn contribution_calendar(&self, variables: &Value) -> (Value, Option<Value>) {
+        let username = variables["username"].as_str().unwrap_or_default();
+        let user = match self.dataset.user(username) {
+            Some(user) => user,
+            None => {
+                return not_found(
+                    "user",
+                    format!(
+                        "Could not resolve to a User with the login of '{}'.",
+                        username
+                    ),
+                )
+            }
+        };
+
+        let weeks: Vec<Value> = user
+            .calendar
+            .chunks(7)
+            .map(|week| {
+                json!({
+                    "firstDay": week[0].date.to_string(),
+                    "contributionDays": week.iter().map(calendar_day_json).collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+
+        (
+            json!({
+                "user": {
+                    "contributionsCollection": {
+                        "contributionCalendar": { "colors": CALENDAR_COLORS, "weeks": weeks }
+                    }
+                }
+            }),
+            None,
+        )
+    }
+
+    fn pull_request_json(&self, pull_request: &FakePullRequest) -> Value {
+        let nested = self.page_sizes.nested;
+
+        json!({
+            "id": pull_request.id,
+            "number": pull_request.number,
+            "title": pull_request.title,
+            "createdAt": pull_request.created_at.to_rfc3339(),
+            "closedAt": pull_request.closed_at.map(|date| date.to_rfc3339()),
+            "mergedAt": pull_request.merged_at.map(|date| date.to_rfc3339()),
+            "merged": pull_request.merged_at.is_some(),
+            "additions": pull_request.additions,
+            "deletions": pull_request.deletions,
+            "changedFiles": pull_request.changed_files,
+            "baseRefName": pull_request.base_ref_name,
+            "headRefName": pull_request.head_ref_name,
+            "author": actor_json(&pull_request.author),
+            "repository": { "nameWithOwner": pull_request.repository },
+            "reviews": {
+                "pageInfo": { "hasNextPage": pull_request.reviews.len() > nested },
+                "nodes": pull_request.reviews.iter().take(nested).map(review_json).collect::<Vec<_>>(),
+            },
+            "commits": {
+                "pageInfo": { "hasNextPage": pull_request.commits.len() > nested },
+                "nodes": pull_request.commits.iter().take(nested).map(commit_json).collect::<Vec<_>>(),
+            },
+        })
+    }
+}
+
+fn paginate<'a, T>(items: &'a [T], cursor: &Value, size: usize) -> (&'a [T], Value) {
+    let start = cursor
+        .as_str()
+        .and_then(|cursor| cursor.strip_prefix("cursor:"))
+        .and_then(|offset| offset.parse().ok())
+        .unwrap_or(0usize)
+        .min(items.len());
+    let end = (start + size.max(1)).min(items.len());
+    let end_cursor = if end > start {
+        json!(format!("cursor:{}", end))
+    } else {
+        Value::Null
+    };
+
+    (
+        &items[start..end],
+        json!({ "endCursor": end_cursor, "hasNextPage": end < items.len() }),
+    )
+}
+
+fn not_found(field: &str, message: String) -> (Value, Option<Value>) {
+    let mut data = json!({});
+    data[field] = Value::Null;
+
+    (
+        data,
+        Some(json!([{
+            "type": "NOT_FOUND",
+            "path": [field],
+            "message": message,
+        }])),
+    )
+}
+
+fn contributions_window(variables: &Value) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+    let from = date_variable(&variables["from"]);
+    let to = date_variable(&variables["to"]);
+    let (from, to) = match (from, to) {
+        (Some(from), Some(to)) => (from, to),
+        (Some(from), None) => (from, (from + Duration::days(365)).min(Utc::now())),
+        (None, Some(to)) => (to - Duration::days(365), to),
+        (None, None) => (Utc::now() - Duration::days(365), Utc::now()),
+    };
+
+    Some((from, to)).filter(|(from, to)| *to - *from <= Duration::days(365))
+}
+
+fn date_variable(value: &Value) -> Option<DateTime<Utc>> {
+    value
+        .as_str()
+        .and_then(|date| DateTime::parse_from_rfc3339(date).ok())
+        .map(|date| date.with_timezone(&Utc))
+}
+
+pub fn calendar_level(count: i64) -> i16 {
+    match count {
+        0 => 0,
+        1..=3 => 1,
+        4..=7 => 2,
+        8..=12 => 3,
+        _ => 4,
+    }
+}
+
+fn calendar_day_json(day: &FakeCalendarDay) -> Value {
+    let level = calendar_level(day.count);
+
+    json!({
+        "color": match level {
+            0 => EMPTY_COLOR,
+            level => CALENDAR_COLORS[level as usize - 1],
+        },
+        "contributionCount": day.count,
+        "date": day.date.to_string(),
+        "weekday": day.date.weekday().num_days_from_sunday(),
+    })
+}
+
+fn actor_json(actor: &FakeActor) -> Value {
+    match actor {
+        FakeActor::User { id, login } => json!({ "__typename": "User", "login": login, "id": id }),
+        FakeActor::Bot { id, login } => json!({ "__typename": "Bot", "login": login, "id": id }),
+        FakeActor::Mannequin { id, login } => {
+            json!({ "__typename": "Mannequin", "login": login, "id": id })
+        }
+        FakeActor::Ghost => Value::Null,
+    }
+}
+
+fn git_actor_json(author: &Option<FakeGitActor>) -> Value {
+    match author {
+        Some(author) => json!({
+            "name": author.name,
+            "email": author.email,
+            "user": author.user.as_ref().map(|(id, login)| json!({ "id": id, "login": login })),
+        }),
+        None => Value::Null,
+    }
+}
+
+fn review_json(review: &FakeReview) -> Value {
+    json!({
+        "id": review.id,
+        "publishedAt": review.published_at.map(|date| date.to_rfc3339()),
+        "viewerDidAuthor": review.viewer_did_author,
+        "body": review.body,
+        "state": review.state,
+        "author": actor_json(&review.author),
+    })
+}
+
+fn commit_json(commit: &FakeCommit) -> Value {
+    json!({
+        "id": commit.id,
+        "commit": {
+            "abbreviatedOid": commit.oid,
+            "messageHeadline": commit.message_headline,
+            "pushedDate": commit.pushed_at.map(|date| date.to_rfc3339()),
+            "author": git_actor_json(&commit.author),
+        },
+    })
+}